@@ -0,0 +1,384 @@
+use crate::{DictionaryEntry, DictionaryResult};
+use rusqlite::{Connection, params};
+use serde_json::json;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnkiField {
+    Word,
+    Reading,
+    PartOfSpeech,
+    Definitions,
+    Synonyms,
+    Source,
+}
+
+impl FromStr for AnkiField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "word" => Ok(AnkiField::Word),
+            "reading" => Ok(AnkiField::Reading),
+            "pos" | "part_of_speech" => Ok(AnkiField::PartOfSpeech),
+            "definitions" => Ok(AnkiField::Definitions),
+            "synonyms" => Ok(AnkiField::Synonyms),
+            "source" => Ok(AnkiField::Source),
+            other => Err(format!(
+                "unknown entry field: {} (expected word, reading, pos, definitions, synonyms or source)",
+                other
+            )),
+        }
+    }
+}
+
+impl AnkiField {
+    /// Field contents as Anki HTML
+    pub fn value(&self, entry: &DictionaryEntry) -> String {
+        match self {
+            AnkiField::Word => escape_html(&entry.headword()),
+            AnkiField::Reading => escape_html(&entry.reading()),
+            AnkiField::PartOfSpeech => escape_html(&entry.part_of_speech),
+            AnkiField::Definitions => {
+                if entry.definitions.len() == 1 {
+                    escape_html(&entry.definitions[0])
+                } else {
+                    entry.definitions.iter()
+                        .enumerate()
+                        .map(|(i, def)| format!("({}) {}", i + 1, escape_html(def)))
+                        .collect::<Vec<_>>()
+                        .join("<br>")
+                }
+            }
+            AnkiField::Synonyms => escape_html(&entry.synonyms.join(", ")),
+            AnkiField::Source => escape_html(&entry.source),
+        }
+    }
+}
+
+/// An Anki note type and which entry field goes into each of its fields
+#[derive(Debug, Clone)]
+pub struct NoteType {
+    pub name: String,
+    pub fields: Vec<(String, AnkiField)>,
+}
+
+impl Default for NoteType {
+    fn default() -> Self {
+        Self {
+            name: "Jisho".to_string(),
+            fields: vec![
+                ("Word".to_string(), AnkiField::Word),
+                ("Reading".to_string(), AnkiField::Reading),
+                ("Part of Speech".to_string(), AnkiField::PartOfSpeech),
+                ("Definitions".to_string(), AnkiField::Definitions),
+                ("Synonyms".to_string(), AnkiField::Synonyms),
+                ("Source".to_string(), AnkiField::Source),
+            ],
+        }
+    }
+}
+
+impl NoteType {
+    /// Parse a field mapping like `Front=word,Back=definitions`
+    pub fn with_field_mapping(mut self, mapping: &str) -> Result<Self, String> {
+        let mut fields = Vec::new();
+
+        for pair in mapping.split(',').filter(|pair| !pair.trim().is_empty()) {
+            let Some((name, field)) = pair.split_once('=') else {
+                return Err(format!("invalid field mapping: {} (expected Name=field)", pair));
+            };
+            fields.push((name.trim().to_string(), field.trim().parse()?));
+        }
+
+        if fields.is_empty() {
+            return Err("field mapping is empty".to_string());
+        }
+
+        self.fields = fields;
+        Ok(self)
+    }
+
+    fn values(&self, entry: &DictionaryEntry) -> Vec<String> {
+        self.fields.iter().map(|(_, field)| field.value(entry)).collect()
+    }
+}
+
+/// Append entries to an Anki-importable TSV file, writing the import header if the file is new.
+/// An existing file must have been written for the same note type and columns.
+pub fn write_tsv(path: &Path, entries: &[DictionaryEntry], note_type: &NoteType) -> DictionaryResult<()> {
+    let header = tsv_header(note_type);
+    let is_new = fs::metadata(path).map(|meta| meta.len() == 0).unwrap_or(true);
+    if !is_new {
+        check_tsv_header(path, &header)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    if is_new {
+        for line in &header {
+            writeln!(file, "{}", line)?;
+        }
+    }
+
+    for entry in entries {
+        let values: Vec<String> = note_type.values(entry).iter()
+            .map(|value| value.replace('\t', " ").replace('\n', "<br>"))
+            .collect();
+        writeln!(file, "{}", values.join("\t"))?;
+    }
+
+    Ok(())
+}
+
+fn tsv_header(note_type: &NoteType) -> Vec<String> {
+    let columns: Vec<&str> = note_type.fields.iter().map(|(name, _)| name.as_str()).collect();
+    vec![
+        "#separator:tab".to_string(),
+        "#html:true".to_string(),
+        format!("#notetype:{}", note_type.name),
+        format!("#columns:{}", columns.join("\t")),
+    ]
+}
+
+/// Appending rows in a different column order would put them in the wrong Anki fields on import
+fn check_tsv_header(path: &Path, header: &[String]) -> DictionaryResult<()> {
+    let existing: Vec<String> = BufReader::new(File::open(path)?)
+        .lines()
+        .map_while(Result::ok)
+        .take_while(|line| line.starts_with('#'))
+        .collect();
+
+    for line in header.iter().filter(|line| line.starts_with("#notetype:") || line.starts_with("#columns:")) {
+        if !existing.contains(line) {
+            let key = &line[..line.find(':').unwrap_or(0)];
+            let found = existing.iter()
+                .find(|existing| existing.starts_with(key))
+                .map(|existing| format!("has {}", existing))
+                .unwrap_or_else(|| format!("has no {} header", key));
+            // Columns are tab separated, which doesn't show in an error message
+            return Err(format!(
+                "{} {} instead of {}; export to a new file or use the same fields",
+                path.display(), found.replace('\t', ","), line.replace('\t', ",")
+            ).into());
+        }
+    }
+    Ok(())
+}
+
+/// Write entries as a standalone `.apkg` package containing one deck
+pub fn write_apkg(path: &Path, entries: &[DictionaryEntry], note_type: &NoteType, deck_name: &str) -> DictionaryResult<()> {
+    let collection_path = env::temp_dir().join(format!("jisho-{}-{}.anki2", process::id(), now_millis()));
+    let result = build_collection(&collection_path, entries, note_type, deck_name)
+        .and_then(|_| package_collection(&collection_path, path));
+    let _ = fs::remove_file(&collection_path);
+    result
+}
+
+fn build_collection(collection_path: &Path, entries: &[DictionaryEntry], note_type: &NoteType, deck_name: &str) -> DictionaryResult<()> {
+    let now = now_millis();
+    let now_secs = now / 1000;
+    let model_id = now;
+    let deck_id = now + 1;
+
+    let connection = Connection::open(collection_path)?;
+    connection.execute_batch(SCHEMA)?;
+
+    let field_names: Vec<&str> = note_type.fields.iter().map(|(name, _)| name.as_str()).collect();
+    let back: Vec<String> = field_names.iter().skip(1)
+        .map(|name| format!("{{{{{}}}}}", name))
+        .collect();
+
+    let models = json!({
+        model_id.to_string(): {
+            "id": model_id,
+            "name": note_type.name,
+            "type": 0,
+            "mod": now_secs,
+            "usn": -1,
+            "sortf": 0,
+            "did": deck_id,
+            "tmpls": [{
+                "name": "Card 1",
+                "ord": 0,
+                "qfmt": format!("{{{{{}}}}}", field_names[0]),
+                "afmt": format!("{{{{FrontSide}}}}<hr id=answer>{}", back.join("<br>")),
+                "did": null,
+                "bqfmt": "",
+                "bafmt": "",
+            }],
+            "flds": field_names.iter().enumerate().map(|(ord, name)| json!({
+                "name": name,
+                "ord": ord,
+                "sticky": false,
+                "rtl": false,
+                "font": "Arial",
+                "size": 20,
+                "media": [],
+            })).collect::<Vec<_>>(),
+            "css": ".card { font-family: arial; font-size: 20px; text-align: center; }",
+            "latexPre": "",
+            "latexPost": "",
+            "tags": [],
+            "vers": [],
+            "req": [[0, "any", [0]]],
+        }
+    });
+
+    let deck = |id: i64, name: &str| json!({
+        "id": id,
+        "name": name,
+        "desc": "",
+        "mod": now_secs,
+        "usn": -1,
+        "collapsed": false,
+        "newToday": [0, 0],
+        "revToday": [0, 0],
+        "lrnToday": [0, 0],
+        "timeToday": [0, 0],
+        "dyn": 0,
+        "conf": 1,
+        "extendNew": 10,
+        "extendRev": 50,
+    });
+    let decks = json!({
+        "1": deck(1, "Default"),
+        deck_id.to_string(): deck(deck_id, deck_name),
+    });
+
+    let conf = json!({
+        "nextPos": entries.len() + 1,
+        "estTimes": true,
+        "activeDecks": [1],
+        "sortType": "noteFld",
+        "timeLim": 0,
+        "sortBackwards": false,
+        "addToCur": true,
+        "curDeck": 1,
+        "newSpread": 0,
+        "dueCounts": true,
+        "curModel": model_id,
+        "collapseTime": 1200,
+    });
+
+    connection.execute(
+        "INSERT INTO col VALUES (1, ?1, ?2, ?3, 11, 0, 0, 0, ?4, ?5, ?6, ?7, '{}')",
+        params![now_secs, now, now, conf.to_string(), models.to_string(), decks.to_string(), DECK_CONFIG],
+    )?;
+
+    for (i, entry) in entries.iter().enumerate() {
+        let note_id = now + i as i64;
+        let values = note_type.values(entry);
+        let fields = values.join("\x1f");
+        let hash = sha1_smol::Sha1::from(format!("{}\x1f{}", note_type.name, fields)).digest().to_string();
+        let checksum = i64::from_str_radix(&sha1_smol::Sha1::from(&values[0]).digest().to_string()[..8], 16)?;
+
+        connection.execute(
+            "INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, '', ?5, ?6, ?7, 0, '')",
+            params![note_id, &hash[..10], model_id, now_secs, fields, values[0], checksum],
+        )?;
+        connection.execute(
+            "INSERT INTO cards VALUES (?1, ?2, ?3, 0, ?4, -1, 0, 0, ?5, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+            params![note_id, note_id, deck_id, now_secs, i as i64 + 1],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn package_collection(collection_path: &Path, path: &Path) -> DictionaryResult<()> {
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = SimpleFileOptions::default();
+
+    zip.start_file("collection.anki2", options)?;
+    zip.write_all(&fs::read(collection_path)?)?;
+    zip.start_file("media", options)?;
+    zip.write_all(b"{}")?;
+    zip.finish()?;
+
+    Ok(())
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+// Legacy (schema 11) collection layout, which every Anki version can import
+const SCHEMA: &str = "
+CREATE TABLE col (id integer primary key, crt integer not null, mod integer not null, scm integer not null,
+    ver integer not null, dty integer not null, usn integer not null, ls integer not null, conf text not null,
+    models text not null, decks text not null, dconf text not null, tags text not null);
+CREATE TABLE notes (id integer primary key, guid text not null, mid integer not null, mod integer not null,
+    usn integer not null, tags text not null, flds text not null, sfld integer not null, csum integer not null,
+    flags integer not null, data text not null);
+CREATE TABLE cards (id integer primary key, nid integer not null, did integer not null, ord integer not null,
+    mod integer not null, usn integer not null, type integer not null, queue integer not null, due integer not null,
+    ivl integer not null, factor integer not null, reps integer not null, lapses integer not null,
+    left integer not null, odue integer not null, odid integer not null, flags integer not null, data text not null);
+CREATE TABLE revlog (id integer primary key, cid integer not null, usn integer not null, ivl integer not null,
+    lastIvl integer not null, factor integer not null, time integer not null, type integer not null);
+CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null);
+CREATE INDEX ix_notes_usn on notes (usn);
+CREATE INDEX ix_cards_usn on cards (usn);
+CREATE INDEX ix_revlog_usn on revlog (usn);
+CREATE INDEX ix_cards_nid on cards (nid);
+CREATE INDEX ix_cards_sched on cards (did, queue, due);
+CREATE INDEX ix_revlog_cid on revlog (cid);
+CREATE INDEX ix_notes_csum on notes (csum);
+";
+
+const DECK_CONFIG: &str = r#"{"1": {"id": 1, "name": "Default", "mod": 0, "usn": 0, "maxTaken": 60, "autoplay": true,
+"timer": 0, "replayq": true, "dyn": false,
+"new": {"bury": true, "delays": [1, 10], "initialFactor": 2500, "ints": [1, 4, 7], "order": 1, "perDay": 20, "separate": true},
+"lapse": {"delays": [10], "leechAction": 0, "leechFails": 8, "minInt": 1, "mult": 0},
+"rev": {"bury": true, "ease4": 1.3, "fuzz": 0.05, "ivlFct": 1, "maxIvl": 36500, "minSpace": 1, "perDay": 100}}}"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> DictionaryEntry {
+        DictionaryEntry::new("正解 [せいかい]".to_string(), "Noun".to_string(), vec!["correct answer".to_string()])
+    }
+
+    #[test]
+    fn appends_only_to_files_with_the_same_header() {
+        let path = env::temp_dir().join(format!("jisho-anki-test-{}.tsv", process::id()));
+        let _ = fs::remove_file(&path);
+        let note_type = NoteType::default().with_field_mapping("Front=word,Back=definitions").unwrap();
+
+        write_tsv(&path, &[entry()], &note_type).unwrap();
+        write_tsv(&path, &[entry()], &note_type).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.matches("#columns:Front\tBack").count(), 1);
+        assert_eq!(contents.matches("正解\tcorrect answer").count(), 2);
+
+        let more = NoteType::default().with_field_mapping("Front=word,Reading=reading,Back=definitions").unwrap();
+        let error = write_tsv(&path, &[entry()], &more).unwrap_err().to_string();
+        assert!(error.contains("has #columns:Front,Back instead of #columns:Front,Reading,Back"), "{}", error);
+
+        let renamed = NoteType { name: "Basic".to_string(), ..note_type.clone() };
+        assert!(write_tsv(&path, &[entry()], &renamed).is_err());
+        // Nothing is appended after a mismatch
+        assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+
+        fs::write(&path, "正解\tcorrect answer\n").unwrap();
+        let error = write_tsv(&path, &[entry()], &note_type).unwrap_err().to_string();
+        assert!(error.contains("has no #notetype header"), "{}", error);
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{Dictionary, DictionaryEntry, DictionaryResult};
+use crate::format::FormatOptions;
+use crate::render::{OutputFormat, Section};
+use crate::session::Session;
+use std::collections::HashSet;
+use std::io::BufRead;
+
+/// Lookups in flight at once unless `--jobs` says otherwise
+pub const DEFAULT_JOBS: usize = 4;
+
+/// Requests per second to each backend unless the config or `--rate` says otherwise
+pub const DEFAULT_RATE: f64 = 2.0;
+
+/// The outcome of looking up one term of a batch
+#[derive(Debug)]
+pub struct BatchResult {
+    pub term: String,
+    pub entries: Vec<DictionaryEntry>,
+    pub error: Option<String>,
+}
+
+impl BatchResult {
+    pub fn is_miss(&self) -> bool {
+        self.entries.is_empty() && self.error.is_none()
+    }
+}
+
+/// Newline-separated terms, skipping blank lines and `#` comments. Repeated terms are kept
+/// once, in the order they first appear.
+pub fn read_terms(reader: impl BufRead) -> DictionaryResult<Vec<String>> {
+    let mut terms: Vec<String> = Vec::new();
+    let mut seen = HashSet::new();
+    for line in reader.lines() {
+        let line = line?;
+        let term = line.trim();
+        if term.is_empty() || term.starts_with('#') || !seen.insert(term.to_string()) {
+            continue;
+        }
+        terms.push(term.to_string());
+    }
+    Ok(terms)
+}
+
+/// Look up every term with at most `jobs` lookups running at once
+pub fn lookup_all(session: &mut Session, dictionary: &dyn Dictionary, terms: &[String], jobs: usize) -> Vec<BatchResult> {
+    session.lookup_many(dictionary, terms, jobs)
+        .into_iter()
+        .zip(terms)
+        .map(|(result, term)| match result {
+            Ok(entries) => BatchResult { term: term.clone(), entries, error: None },
+            Err(e) => BatchResult { term: term.clone(), entries: Vec::new(), error: Some(e.to_string()) },
+        })
+        .collect()
+}
+
+/// All successful lookups in one document, each under its term
+pub fn format_results(results: &[BatchResult], format: OutputFormat, options: &FormatOptions) -> String {
+    let sections: Vec<Section> = results.iter()
+        .filter(|result| result.error.is_none())
+        .map(|result| Section { title: &result.term, entries: &result.entries })
+        .collect();
+    format.renderer().render_sections(&sections, options)
+}
+
+/// Summary of the terms that found nothing or failed, if any
+pub fn report_misses(results: &[BatchResult]) -> Option<String> {
+    let misses: Vec<&str> = results.iter()
+        .filter(|result| result.is_miss())
+        .map(|result| result.term.as_str())
+        .collect();
+    let failures: Vec<String> = results.iter()
+        .filter_map(|result| Some(format!("  {}: {}", result.term, result.error.as_ref()?)))
+        .collect();
+
+    let mut report = Vec::new();
+    if !misses.is_empty() {
+        report.push(format!("No match for {} of {} terms: {}", misses.len(), results.len(), misses.join(", ")));
+    }
+    if !failures.is_empty() {
+        report.push(format!("Lookup failed for {} terms:\n{}", failures.len(), failures.join("\n")));
+    }
+    Some(report.join("\n")).filter(|report| !report.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_each_term_once_in_order() {
+        let input = "正解\n\n# comment\n  思う  \n正解\n思う\n依存\n";
+        assert_eq!(read_terms(input.as_bytes()).unwrap(), ["正解", "思う", "依存"]);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{Dictionary, DictionaryResult, debug};
+use crate::format::{FormatOptions, format_entries};
+use crate::furigana::is_kana;
+use crate::session::Session;
+use crate::theme;
+use std::collections::HashSet;
+use std::env;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+/// Longest clipboard text that is treated as a word to look up
+const MAX_TERM_CHARS: usize = 50;
+
+/// An external command that prints the clipboard contents, e.g. `wl-paste` or `xclip`
+pub struct ClipboardProvider {
+    program: String,
+    args: Vec<String>,
+}
+
+impl ClipboardProvider {
+    /// Use `$JISHO_CLIPBOARD_CMD` if set, else the first available of wl-paste (Wayland),
+    /// xclip, xsel (X11) and pbpaste
+    pub fn detect() -> Option<Self> {
+        if let Ok(command) = env::var("JISHO_CLIPBOARD_CMD") {
+            let mut parts = command.split_whitespace().map(str::to_string);
+            let program = parts.next()?;
+            return Some(Self { program, args: parts.collect() });
+        }
+
+        let wayland = env::var_os("WAYLAND_DISPLAY").is_some();
+        let candidates: &[(&str, &[&str])] = &[
+            ("wl-paste", &["--no-newline"]),
+            ("xclip", &["-selection", "clipboard", "-o"]),
+            ("xsel", &["--clipboard", "--output"]),
+            ("pbpaste", &[]),
+        ];
+
+        candidates.iter()
+            .filter(|(program, _)| wayland || *program != "wl-paste")
+            .find(|(program, _)| command_exists(program))
+            .map(|(program, args)| Self {
+                program: program.to_string(),
+                args: args.iter().map(|arg| arg.to_string()).collect(),
+            })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.program
+    }
+
+    pub fn read(&self) -> DictionaryResult<String> {
+        let output = Command::new(&self.program).args(&self.args).output()?;
+        // An empty clipboard makes some providers exit with an error
+        if !output.status.success() {
+            return Ok(String::new());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+fn command_exists(program: &str) -> bool {
+    env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+pub fn is_japanese(text: &str) -> bool {
+    text.chars().any(|ch| is_kana(ch) || matches!(ch, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '々'))
+}
+
+/// Poll the clipboard and look up every new piece of Japanese text, skipping terms
+/// already shown in this session
+pub fn watch(
+    provider: &ClipboardProvider,
+    session: &mut Session,
+    dictionary: &dyn Dictionary,
+    format: &FormatOptions,
+    interval: Duration,
+) -> DictionaryResult<()> {
+    let mut last_clipboard = provider.read()?;
+    let mut seen = HashSet::new();
+
+    let theme = theme::current();
+    println!("{}", theme.heading.paint(&format!("Watching the clipboard via {} (Ctrl-C to stop)", provider.name())));
+    loop {
+        thread::sleep(interval);
+
+        // A busy clipboard owner or a briefly failing command shouldn't end the session
+        let clipboard = match provider.read() {
+            Ok(clipboard) => clipboard,
+            Err(e) => {
+                debug(&format!("clipboard: could not read via {}: {}", provider.name(), e));
+                continue;
+            }
+        };
+        if clipboard == last_clipboard {
+            continue;
+        }
+        last_clipboard = clipboard.clone();
+
+        let term = clipboard.trim();
+        if term.is_empty() || term.chars().count() > MAX_TERM_CHARS || !is_japanese(term) {
+            continue;
+        }
+        if seen.contains(term) {
+            continue;
+        }
+
+        println!("{}", theme.heading.paint(&format!("── {} ──", term)));
+        match session.lookup(dictionary, term) {
+            Ok(entries) => {
+                println!("\n{}\n", format_entries(&entries, format));
+                seen.insert(term.to_string());
+            }
+            // Not marked as seen, so copying the word again retries it
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+}
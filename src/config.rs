@@ -0,0 +1,270 @@
+use crate::{BACKENDS, DictionaryResult, http, is_backend, offline, pitch_accent};
+use crate::furigana::FuriganaStyle;
+use crate::render::OutputFormat;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// HTTP timeout unless the config says otherwise
+pub const DEFAULT_HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Settings from the config file, with `$JISHO_THEME`, `$JISHO_ACCENT_DICT` and
+/// `$JISHO_EDICT` taking precedence over it. Command line flags override both.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// File the settings were read from, if it exists
+    pub path: Option<PathBuf>,
+    pub backend: String,
+    /// Backends tried in order after `backend` when it fails or finds nothing
+    pub fallback: Vec<String>,
+    pub format: OutputFormat,
+    pub theme: String,
+    pub furigana: Option<FuriganaStyle>,
+    /// How long lookups stay cached in a session, forever if None
+    pub cache_ttl: Option<Duration>,
+    /// Most entries shown per lookup, all if None
+    pub limit: Option<usize>,
+    /// Weblio sub-dictionaries to show entries from, all if empty
+    pub dictionaries: Vec<String>,
+    pub accent_dict: Option<PathBuf>,
+    pub edict: Option<PathBuf>,
+    pub http_timeout: Duration,
+    /// `http_timeout.<backend>` overrides, e.g. a shorter one for a backend with a fallback
+    pub backend_timeouts: HashMap<String, Duration>,
+    pub http_retries: u32,
+    /// Most requests per second to each backend, unlimited if None
+    pub rate_limit: Option<f64>,
+    /// User-Agent header, `http::DEFAULT_USER_AGENT` if None
+    pub user_agent: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            path: None,
+            backend: "wwwjdic".to_string(),
+            fallback: Vec::new(),
+            format: OutputFormat::Text,
+            theme: "dark".to_string(),
+            furigana: None,
+            cache_ttl: None,
+            limit: None,
+            dictionaries: Vec::new(),
+            accent_dict: None,
+            edict: None,
+            http_timeout: DEFAULT_HTTP_TIMEOUT,
+            backend_timeouts: HashMap::new(),
+            http_retries: http::DEFAULT_RETRIES,
+            rate_limit: None,
+            user_agent: None,
+        }
+    }
+}
+
+impl Config {
+    /// Read the config file at `default_path` (if there is one) and apply environment overrides
+    pub fn load() -> DictionaryResult<Self> {
+        let mut config = match default_path() {
+            Some(path) if path.exists() => Self::load_file(&path)?,
+            _ => Self::default(),
+        };
+
+        if let Some(theme) = env::var("JISHO_THEME").ok().filter(|theme| !theme.is_empty()) {
+            config.theme = theme;
+        }
+        if let Some(path) = env::var_os("JISHO_ACCENT_DICT").filter(|path| !path.is_empty()) {
+            config.accent_dict = Some(PathBuf::from(path));
+        }
+        if let Some(path) = env::var_os("JISHO_EDICT").filter(|path| !path.is_empty()) {
+            config.edict = Some(PathBuf::from(path));
+        }
+        Ok(config)
+    }
+
+    pub fn load_file(path: &Path) -> DictionaryResult<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("could not read config {}: {}", path.display(), e))?;
+        let mut config = Self::parse(&contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+        config.path = Some(path.to_path_buf());
+        Ok(config)
+    }
+
+    /// `key = value` lines; `#` starts a comment
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| format!("line {}: expected key = value", number + 1))?;
+            config.set(key.trim(), value.trim()).map_err(|e| format!("line {}: {}", number + 1, e))?;
+        }
+        Ok(config)
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "backend" => {
+                check_backend(value)?;
+                self.backend = value.to_string();
+            }
+            "fallback" => {
+                let fallback = parse_list(value);
+                for backend in &fallback {
+                    check_backend(backend)?;
+                }
+                self.fallback = fallback;
+            }
+            "format" => self.format = value.parse()?,
+            "theme" => self.theme = value.to_string(),
+            "furigana" if value == "off" => self.furigana = None,
+            "furigana" => self.furigana = Some(value.parse()?),
+            // 0 means no expiry / no limit
+            "cache_ttl" => self.cache_ttl = Some(parse_seconds(value)?).filter(|ttl| !ttl.is_zero()),
+            "limit" => self.limit = Some(parse_number(value)?).filter(|&limit| limit > 0),
+            "dictionaries" => self.dictionaries = parse_list(value),
+            "accent_dict" => self.accent_dict = Some(expand_home(value)),
+            "edict" => self.edict = Some(expand_home(value)),
+            "http_timeout" => self.http_timeout = parse_timeout(value)?,
+            "http_retries" => self.http_retries = parse_retries(value)?,
+            "rate_limit" => self.rate_limit = Some(parse_rate(value)?).filter(|&rate| rate > 0.0),
+            key if key.starts_with("http_timeout.") => {
+                let backend = &key["http_timeout.".len()..];
+                check_backend(backend)?;
+                self.backend_timeouts.insert(backend.to_string(), parse_timeout(value)?);
+            }
+            "user_agent" => self.user_agent = Some(value.to_string()).filter(|agent| !agent.is_empty()),
+            other => return Err(format!("unknown setting: {}", other)),
+        }
+        Ok(())
+    }
+
+    /// The effective settings in config file syntax, with defaults filled in
+    pub fn describe(&self) -> String {
+        let path_or = |path: &Option<PathBuf>, default: Option<PathBuf>| {
+            path.clone().or(default).map(|path| path.display().to_string()).unwrap_or_default()
+        };
+        let furigana = self.furigana.map(|style| style.name()).unwrap_or("off");
+
+        let settings = [
+            ("backend", self.backend.clone()),
+            ("fallback", self.fallback.join(", ")),
+            ("format", self.format.name().to_string()),
+            ("theme", self.theme.clone()),
+            ("furigana", furigana.to_string()),
+            ("cache_ttl", self.cache_ttl.map(|ttl| ttl.as_secs()).unwrap_or(0).to_string()),
+            ("limit", self.limit.unwrap_or(0).to_string()),
+            ("dictionaries", self.dictionaries.join(",")),
+            ("accent_dict", path_or(&self.accent_dict, pitch_accent::default_path())),
+            ("edict", path_or(&self.edict, offline::default_path())),
+            ("http_timeout", self.http_timeout.as_secs().to_string()),
+            ("http_retries", self.http_retries.to_string()),
+            ("rate_limit", self.rate_limit.unwrap_or(0.0).to_string()),
+            ("user_agent", self.user_agent.clone().unwrap_or_else(|| http::DEFAULT_USER_AGENT.to_string())),
+        ];
+        let mut lines: Vec<String> = settings.iter()
+            .map(|(key, value)| format!("{} = {}", key, value))
+            .collect();
+
+        let mut backend_timeouts: Vec<_> = self.backend_timeouts.iter().collect();
+        backend_timeouts.sort();
+        for (backend, timeout) in backend_timeouts {
+            lines.push(format!("http_timeout.{} = {}", backend, timeout.as_secs()));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Config file location: `$JISHO_CONFIG`, else `jisho/config` in `$XDG_CONFIG_HOME` or `~/.config`
+pub fn default_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("JISHO_CONFIG").filter(|path| !path.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir).join("jisho/config"));
+    }
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".config/jisho/config"))
+}
+
+fn check_backend(name: &str) -> Result<(), String> {
+    if is_backend(name) {
+        Ok(())
+    } else {
+        Err(format!("unknown backend: {} (expected {})", name, BACKENDS.join(", ")))
+    }
+}
+
+/// Comma-separated names, e.g. `大辞泉,大辞林`
+pub fn parse_list(value: &str) -> Vec<String> {
+    value.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn parse_number(value: &str) -> Result<usize, String> {
+    value.parse().map_err(|_| format!("expected a number, got {}", value))
+}
+
+/// Retry count up to `http::MAX_RETRIES`
+fn parse_retries(value: &str) -> Result<u32, String> {
+    match u32::try_from(parse_number(value)?) {
+        Ok(retries) if retries <= http::MAX_RETRIES => Ok(retries),
+        _ => Err(format!("http_retries must be at most {}, got {}", http::MAX_RETRIES, value)),
+    }
+}
+
+/// Requests per second, e.g. `2` or `0.5`
+fn parse_rate(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(rate) if rate >= 0.0 && rate.is_finite() => Ok(rate),
+        _ => Err(format!("expected requests per second, got {}", value)),
+    }
+}
+
+/// Whole seconds, optionally written with an `s` suffix
+fn parse_seconds(value: &str) -> Result<Duration, String> {
+    let seconds = value.strip_suffix('s').unwrap_or(value);
+    Ok(Duration::from_secs(parse_number(seconds)? as u64))
+}
+
+/// Seconds like `parse_seconds`, but not 0, which would make every request time out at once
+fn parse_timeout(value: &str) -> Result<Duration, String> {
+    match parse_seconds(value)? {
+        timeout if timeout.is_zero() => Err("http_timeout must be at least 1 second".to_string()),
+        timeout => Ok(timeout),
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_zero_timeouts() {
+        assert!(Config::parse("http_timeout = 0").is_err());
+        assert!(Config::parse("http_timeout.weblio = 0s").is_err());
+
+        let config = Config::parse("http_timeout = 5s\nhttp_timeout.weblio = 10").unwrap();
+        assert_eq!(config.http_timeout, Duration::from_secs(5));
+        assert_eq!(config.backend_timeouts["weblio"], Duration::from_secs(10));
+    }
+
+    #[test]
+    fn treats_zero_cache_ttl_as_no_expiry() {
+        assert_eq!(Config::parse("cache_ttl = 0").unwrap().cache_ttl, None);
+    }
+}
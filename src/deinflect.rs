@@ -0,0 +1,272 @@
+use crate::DictionaryEntry;
+use std::sync::OnceLock;
+
+// Word types a form can conjugate as. INITIAL marks the text as it was written,
+// before any rule has been applied.
+pub const INITIAL: u8 = 1;
+pub const V1: u8 = 1 << 1;
+pub const V5: u8 = 1 << 2;
+pub const VK: u8 = 1 << 3;
+pub const VS: u8 = 1 << 4;
+pub const ADJ_I: u8 = 1 << 5;
+const WORD_TYPES: u8 = V1 | V5 | VK | VS | ADJ_I;
+
+/// A possible dictionary form of an inflected word
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deinflection {
+    pub word: String,
+    /// Word types the dictionary form must have, checked against the entry's part of speech
+    pub word_type: u8,
+    /// Inflections undone to get here, outermost first, e.g. ["past", "passive"]
+    pub reasons: Vec<&'static str>,
+}
+
+impl Deinflection {
+    /// Whether a dictionary entry for `self.word` can actually be inflected this way
+    pub fn matches(&self, entry: &DictionaryEntry) -> bool {
+        if self.reasons.is_empty() {
+            return true;
+        }
+
+        let pos = &entry.part_of_speech;
+        (self.word_type & V1 != 0 && pos.contains("v1"))
+            || (self.word_type & V5 != 0 && pos.contains("v5"))
+            || (self.word_type & VK != 0 && pos.contains("vk"))
+            || (self.word_type & VS != 0 && pos.contains("vs"))
+            || (self.word_type & ADJ_I != 0 && pos.contains("adj-i"))
+    }
+}
+
+struct Rule {
+    from: String,
+    to: String,
+    /// Types the inflected form must have for the rule to apply
+    from_type: u8,
+    to_type: u8,
+    reason: &'static str,
+}
+
+/// All dictionary forms `word` could be an inflection of, including `word` itself
+pub fn deinflect(word: &str) -> Vec<Deinflection> {
+    static RULES: OnceLock<Vec<Rule>> = OnceLock::new();
+    let rules = RULES.get_or_init(rules);
+    let mut results = vec![Deinflection {
+        word: word.to_string(),
+        word_type: INITIAL | WORD_TYPES,
+        reasons: Vec::new(),
+    }];
+
+    let mut i = 0;
+    while i < results.len() {
+        let current = results[i].clone();
+        i += 1;
+
+        for rule in rules {
+            if current.word_type & rule.from_type == 0 || !current.word.ends_with(&rule.from) {
+                continue;
+            }
+
+            let stem = &current.word[..current.word.len() - rule.from.len()];
+            if stem.is_empty() && rule.to.chars().count() <= 1 {
+                continue;
+            }
+            let word = format!("{}{}", stem, rule.to);
+
+            if results.iter().any(|result| result.word == word && result.word_type & rule.to_type != 0) {
+                continue;
+            }
+
+            let mut reasons = current.reasons.clone();
+            reasons.push(rule.reason);
+            results.push(Deinflection { word, word_type: rule.to_type, reasons });
+        }
+    }
+
+    results
+}
+
+fn rule(rules: &mut Vec<Rule>, from: &str, to: &str, from_type: u8, to_type: u8, reason: &'static str) {
+    rules.push(Rule {
+        from: from.to_string(),
+        to: to.to_string(),
+        from_type,
+        to_type,
+        reason,
+    });
+}
+
+fn rules() -> Vec<Rule> {
+    let mut rules = Vec::new();
+
+    // Godan verbs: dictionary ending, a/i/e/o rows, te and ta forms
+    let godan: &[(&str, &str, &str, &str, &str, &str, &str)] = &[
+        ("う", "わ", "い", "え", "お", "って", "った"),
+        ("く", "か", "き", "け", "こ", "いて", "いた"),
+        ("ぐ", "が", "ぎ", "げ", "ご", "いで", "いだ"),
+        ("す", "さ", "し", "せ", "そ", "して", "した"),
+        ("つ", "た", "ち", "て", "と", "って", "った"),
+        ("ぬ", "な", "に", "ね", "の", "んで", "んだ"),
+        ("ぶ", "ば", "び", "べ", "ぼ", "んで", "んだ"),
+        ("む", "ま", "み", "め", "も", "んで", "んだ"),
+        ("る", "ら", "り", "れ", "ろ", "って", "った"),
+    ];
+    for &(u, a, i, e, o, te, ta) in godan {
+        rule(&mut rules, &format!("{}ない", a), u, ADJ_I, V5, "negative");
+        rule(&mut rules, &format!("{}ます", i), u, INITIAL, V5, "polite");
+        rule(&mut rules, &format!("{}ました", i), u, INITIAL, V5, "polite past");
+        rule(&mut rules, &format!("{}ません", i), u, INITIAL, V5, "polite negative");
+        rule(&mut rules, &format!("{}ませんでした", i), u, INITIAL, V5, "polite past negative");
+        rule(&mut rules, &format!("{}ましょう", i), u, INITIAL, V5, "polite volitional");
+        rule(&mut rules, &format!("{}たい", i), u, ADJ_I, V5, "want");
+        rule(&mut rules, &format!("{}ながら", i), u, INITIAL, V5, "while");
+        rule(&mut rules, &format!("{}ば", e), u, INITIAL, V5, "conditional");
+        rule(&mut rules, &format!("{}る", e), u, V1, V5, "potential");
+        rule(&mut rules, e, u, INITIAL, V5, "imperative");
+        rule(&mut rules, &format!("{}う", o), u, INITIAL, V5, "volitional");
+        rule(&mut rules, &format!("{}れる", a), u, V1, V5, "passive");
+        rule(&mut rules, &format!("{}せる", a), u, V1, V5, "causative");
+        rule(&mut rules, te, u, INITIAL, V5, "te-form");
+        rule(&mut rules, ta, u, INITIAL, V5, "past");
+        rule(&mut rules, &format!("{}ら", ta), u, INITIAL, V5, "conditional");
+        rule(&mut rules, &format!("{}り", ta), u, INITIAL, V5, "tari");
+    }
+
+    // 行く is the one godan verb with an irregular te/ta form
+    for (from, to) in [("行って", "行く"), ("いって", "いく")] {
+        rule(&mut rules, from, to, INITIAL, V5, "te-form");
+    }
+    for (from, to) in [("行った", "行く"), ("いった", "いく")] {
+        rule(&mut rules, from, to, INITIAL, V5, "past");
+    }
+
+    // Ichidan verbs
+    let ichidan: &[(&str, u8, &'static str)] = &[
+        ("ない", ADJ_I, "negative"),
+        ("ます", INITIAL, "polite"),
+        ("ました", INITIAL, "polite past"),
+        ("ません", INITIAL, "polite negative"),
+        ("ませんでした", INITIAL, "polite past negative"),
+        ("ましょう", INITIAL, "polite volitional"),
+        ("たい", ADJ_I, "want"),
+        ("ながら", INITIAL, "while"),
+        ("れば", INITIAL, "conditional"),
+        ("られる", V1, "passive/potential"),
+        ("させる", V1, "causative"),
+        ("よう", INITIAL, "volitional"),
+        ("ろ", INITIAL, "imperative"),
+        ("て", INITIAL, "te-form"),
+        ("た", INITIAL, "past"),
+        ("たら", INITIAL, "conditional"),
+        ("たり", INITIAL, "tari"),
+    ];
+    for &(from, from_type, reason) in ichidan {
+        rule(&mut rules, from, "る", from_type, V1, reason);
+    }
+
+    // Auxiliaries that attach to the te-form and conjugate as ichidan verbs
+    for (from, to) in [("ている", "て"), ("でいる", "で"), ("てる", "て"), ("でる", "で")] {
+        rule(&mut rules, from, to, V1, INITIAL, "progressive");
+    }
+
+    // i-adjectives
+    let adjective: &[(&str, u8, &'static str)] = &[
+        ("かった", INITIAL, "past"),
+        ("くない", ADJ_I, "negative"),
+        ("くて", INITIAL, "te-form"),
+        ("ければ", INITIAL, "conditional"),
+        ("かったら", INITIAL, "conditional"),
+        ("く", INITIAL, "adverb"),
+        ("さ", INITIAL, "noun"),
+        ("そう", INITIAL, "seemingly"),
+    ];
+    for &(from, from_type, reason) in adjective {
+        rule(&mut rules, from, "い", from_type, ADJ_I, reason);
+    }
+
+    // する and 来る
+    let irregular: &[(&str, &str, u8, u8, &'static str)] = &[
+        ("しない", "する", ADJ_I, VS, "negative"),
+        ("します", "する", INITIAL, VS, "polite"),
+        ("しました", "する", INITIAL, VS, "polite past"),
+        ("しません", "する", INITIAL, VS, "polite negative"),
+        ("したい", "する", ADJ_I, VS, "want"),
+        ("して", "する", INITIAL, VS, "te-form"),
+        ("した", "する", INITIAL, VS, "past"),
+        ("すれば", "する", INITIAL, VS, "conditional"),
+        ("しよう", "する", INITIAL, VS, "volitional"),
+        ("しろ", "する", INITIAL, VS, "imperative"),
+        ("される", "する", V1, VS, "passive"),
+        ("させる", "する", V1, VS, "causative"),
+        ("できる", "する", V1, VS, "potential"),
+        ("こない", "くる", ADJ_I, VK, "negative"),
+        ("来ない", "来る", ADJ_I, VK, "negative"),
+        ("きます", "くる", INITIAL, VK, "polite"),
+        ("来ます", "来る", INITIAL, VK, "polite"),
+        ("きて", "くる", INITIAL, VK, "te-form"),
+        ("来て", "来る", INITIAL, VK, "te-form"),
+        ("きた", "くる", INITIAL, VK, "past"),
+        ("来た", "来る", INITIAL, VK, "past"),
+        ("こよう", "くる", INITIAL, VK, "volitional"),
+        ("来よう", "来る", INITIAL, VK, "volitional"),
+    ];
+    for &(from, to, from_type, to_type, reason) in irregular {
+        rule(&mut rules, from, to, from_type, to_type, reason);
+    }
+
+    rules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find<'a>(results: &'a [Deinflection], word: &str) -> Option<&'a Deinflection> {
+        results.iter().find(|result| result.word == word)
+    }
+
+    #[test]
+    fn keeps_the_word_itself() {
+        let results = deinflect("食べる");
+        assert_eq!(results[0].word, "食べる");
+        assert!(results[0].reasons.is_empty());
+    }
+
+    #[test]
+    fn undoes_negative_past_of_ichidan_verb() {
+        let results = deinflect("食べなかった");
+        let result = find(&results, "食べる").expect("食べる");
+        assert_eq!(result.reasons, ["past", "negative"]);
+        assert_ne!(result.word_type & V1, 0);
+    }
+
+    #[test]
+    fn undoes_irregular_te_form_of_iku() {
+        let results = deinflect("行って");
+        let result = find(&results, "行く").expect("行く");
+        assert_eq!(result.reasons, ["te-form"]);
+        assert_ne!(result.word_type & V5, 0);
+    }
+
+    #[test]
+    fn undoes_godan_polite_and_adjective_forms() {
+        assert_eq!(find(&deinflect("読みました"), "読む").unwrap().reasons, ["polite past"]);
+        assert_eq!(find(&deinflect("高くない"), "高い").unwrap().reasons, ["negative"]);
+        assert_eq!(find(&deinflect("勉強して"), "勉強する").unwrap().reasons, ["te-form"]);
+    }
+
+    #[test]
+    fn undoes_progressive_after_te_form() {
+        let results = deinflect("食べている");
+        assert_eq!(find(&results, "食べる").unwrap().reasons, ["progressive", "te-form"]);
+    }
+
+    #[test]
+    fn checks_part_of_speech_of_the_dictionary_form() {
+        let results = deinflect("食べなかった");
+        let deinflection = find(&results, "食べる").unwrap();
+        let verb = DictionaryEntry::new("食べる [たべる]".to_string(), "(v1,vt)".to_string(), Vec::new());
+        let noun = DictionaryEntry::new("食べる [たべる]".to_string(), "(n)".to_string(), Vec::new());
+        assert!(deinflection.matches(&verb));
+        assert!(!deinflection.matches(&noun));
+    }
+}
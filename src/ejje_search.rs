@@ -0,0 +1,115 @@
+use crate::{Dictionary, DictionaryEntry, DictionaryResult};
+use crate::format::{FormatOptions, format_entries};
+use crate::http;
+use crate::sense::ExampleSentence;
+use scraper::{ElementRef, Html, Selector};
+
+pub const SOURCE: &str = "ejje";
+
+/// Weblio's English-Japanese dictionary (ejje.weblio.jp). English terms are looked up
+/// in 英和, anything containing Japanese in 和英.
+pub struct Ejje;
+
+impl Dictionary for Ejje {
+    fn name(&self) -> &'static str {
+        SOURCE
+    }
+
+    fn lookup(&self, term: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
+        lookup_ejje(term)
+    }
+}
+
+pub fn search_ejje(term: &str) -> DictionaryResult<String> {
+    let entries = lookup_ejje(term)?;
+    Ok(format_entries(&entries, &FormatOptions::default()))
+}
+
+pub fn lookup_ejje(term: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
+    let url = format!("https://ejje.weblio.jp/content/{}", term);
+
+    let Some(html) = http::get_page(SOURCE, &url)? else {
+        return Ok(Vec::new());
+    };
+
+    let document = Html::parse_document(&html);
+
+    Ok(parse_ejje_entry(&document, term).into_iter().collect())
+}
+
+/// The summary translations at the top of the page, plus the example sentences below them
+fn parse_ejje_entry(document: &Html, term: &str) -> Option<DictionaryEntry> {
+    let explanation_selector = Selector::parse(".content-explanation").unwrap();
+    let explanation = document.select(&explanation_selector)
+        .map(|element| element.text().collect::<String>())
+        .find(|text| !text.trim().is_empty())?;
+
+    let translations = split_translations(&explanation);
+    if translations.is_empty() {
+        return None;
+    }
+
+    let dictionary = if is_japanese(term) { "和英" } else { "英和" };
+    let entry = DictionaryEntry::new(term.to_string(), String::new(), translations)
+        .with_example_sentences(extract_example_sentences(document))
+        .with_source(SOURCE)
+        .with_dictionary(dictionary);
+    Some(entry)
+}
+
+/// "正しい、正確な；適切な" or "correct answer; right answer" as separate translations.
+/// Commas are left alone, since one English translation can contain them ("well, then").
+fn split_translations(explanation: &str) -> Vec<String> {
+    let mut translations: Vec<String> = Vec::new();
+    for translation in explanation.split(['、', '・', '／', '；', ';']) {
+        let translation = translation.trim();
+        if !translation.is_empty() && !translations.iter().any(|existing| existing == translation) {
+            translations.push(translation.to_string());
+        }
+    }
+    translations
+}
+
+/// Example pairs: `div.qotC` blocks with the English in `p.qotCE` and the Japanese in `p.qotCJ`
+fn extract_example_sentences(document: &Html) -> Vec<ExampleSentence> {
+    let example_selector = Selector::parse("div.qotC").unwrap();
+    let english_selector = Selector::parse("p.qotCE").unwrap();
+    let japanese_selector = Selector::parse("p.qotCJ").unwrap();
+
+    document.select(&example_selector)
+        .filter_map(|example| {
+            let english = example.select(&english_selector).next().map(|p| example_text(&p))?;
+            let japanese = example.select(&japanese_selector).next().map(|p| example_text(&p)).unwrap_or_default();
+            if english.is_empty() {
+                return None;
+            }
+            Some(ExampleSentence { text: english, translation: japanese })
+        })
+        .collect()
+}
+
+/// Sentence text without the "例文帳に追加" button and the "- 研究社 新英和中辞典" credit
+fn example_text(element: &ElementRef) -> String {
+    let text = element.text().collect::<String>().replace("例文帳に追加", "");
+    let text = match text.rfind(" - ") {
+        Some(credit) => &text[..credit],
+        None => &text,
+    };
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn is_japanese(term: &str) -> bool {
+    term.chars().any(|c| matches!(c, '\u{3040}'..='\u{30FF}' | '\u{3400}'..='\u{9FFF}' | '\u{FF66}'..='\u{FF9F}'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_translations_but_not_on_commas() {
+        assert_eq!(split_translations("正しい、正確な；適切な・妥当な／正しい"), ["正しい", "正確な", "適切な", "妥当な"]);
+        assert_eq!(split_translations("correct answer; right answer"), ["correct answer", "right answer"]);
+        assert_eq!(split_translations("well, then; so"), ["well, then", "so"]);
+    }
+}
@@ -0,0 +1,52 @@
+use crate::{Dictionary, DictionaryEntry, DictionaryResult};
+
+/// Backends tried in order until one answers: the next one is asked when a lookup fails
+/// or finds nothing. Entries keep the `source` of the backend that answered.
+pub struct Fallback {
+    dictionaries: Vec<Box<dyn Dictionary>>,
+}
+
+impl Fallback {
+    pub fn new(dictionaries: Vec<Box<dyn Dictionary>>) -> Self {
+        Self { dictionaries }
+    }
+}
+
+impl Dictionary for Fallback {
+    /// The first backend's name, which the chain stands in for in prompts and messages
+    fn name(&self) -> &'static str {
+        self.dictionaries.first().map(|dictionary| dictionary.name()).unwrap_or("fallback")
+    }
+
+    fn backends(&self) -> Vec<&'static str> {
+        self.dictionaries.iter().flat_map(|dictionary| dictionary.backends()).collect()
+    }
+
+    fn lookup(&self, term: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
+        let mut errors = Vec::new();
+        for dictionary in &self.dictionaries {
+            match dictionary.lookup(term) {
+                Ok(entries) if !entries.is_empty() => return Ok(entries),
+                Ok(_) => {}
+                Err(e) => errors.push(format!("{}: {}", dictionary.name(), e)),
+            }
+        }
+
+        // Only an error if no backend could be asked at all
+        if !errors.is_empty() && errors.len() == self.dictionaries.len() {
+            return Err(errors.join("; ").into());
+        }
+        Ok(Vec::new())
+    }
+}
+
+/// Backends that answered, in order of appearance
+pub fn sources(entries: &[DictionaryEntry]) -> Vec<&str> {
+    let mut sources = Vec::new();
+    for entry in entries {
+        if !entry.source.is_empty() && !sources.contains(&entry.source.as_str()) {
+            sources.push(entry.source.as_str());
+        }
+    }
+    sources
+}
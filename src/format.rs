@@ -0,0 +1,286 @@
+use crate::DictionaryEntry;
+use crate::furigana::{self, FuriganaStyle};
+use crate::pitch_accent;
+use crate::reference::ReferenceKind;
+use crate::sense::Sense;
+use crate::theme::{self, Style};
+use crate::wrap;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FormatOptions {
+    /// Show the headword with furigana instead of the raw word/reading string
+    pub furigana: Option<FuriganaStyle>,
+    /// Sense to mark, when showing the target of a followed reference
+    pub highlight_sense: Option<usize>,
+    /// Wrap definitions to this many columns
+    pub width: Option<usize>,
+}
+
+pub fn format_entries(entries: &[DictionaryEntry], options: &FormatOptions) -> String {
+    if entries.is_empty() {
+        "No definitions found.".to_string()
+    } else {
+        // References are numbered across the whole result so they can be followed by number
+        let mut first_reference = 1;
+        let formatted_entries: Vec<String> = entries.iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let mut formatted = format_entry_numbered(entry, options, first_reference);
+                if starts_dictionary_group(entries, i) {
+                    let label = theme::current().heading.paint(&format!("〈{}〉", entry.dictionary));
+                    formatted = format!("{}\n{}", label, formatted);
+                }
+                first_reference += entry.references.len();
+                formatted
+            })
+            .collect();
+        formatted_entries.join("\n\n")
+    }
+}
+
+/// Whether `entries[index]` is the first of a run from one sub-dictionary, so it gets a label
+pub fn starts_dictionary_group(entries: &[DictionaryEntry], index: usize) -> bool {
+    let dictionary = &entries[index].dictionary;
+    !dictionary.is_empty() && (index == 0 || entries[index - 1].dictionary != *dictionary)
+}
+
+pub fn format_entry(entry: &DictionaryEntry, options: &FormatOptions) -> String {
+    format_entry_numbered(entry, options, 1)
+}
+
+fn format_entry_numbered(entry: &DictionaryEntry, options: &FormatOptions, first_reference: usize) -> String {
+    let theme = theme::current();
+    let mut result = String::new();
+
+    // Word and reading
+    for line in format_headword(entry, options) {
+        result.push_str(&theme.headword.paint(&line).to_string());
+        result.push('\n');
+    }
+
+    // Notation variants, historical kana and etymology on one line
+    let metadata = entry.metadata();
+    if !metadata.is_empty() {
+        push_wrapped(&mut result, "", String::new(), &metadata_line(&metadata), theme.muted, options.width);
+        result.push('\n');
+    }
+
+    // Pitch accent under the headword (if known)
+    if !entry.pitch_accent.is_empty() {
+        let reading = entry.reading();
+        let accents: Vec<String> = entry.pitch_accent.iter()
+            .map(|&pattern| pitch_accent::describe(&reading, pattern))
+            .collect();
+        result.push_str(&theme.accent.paint(&accents.join(" / ")).to_string());
+        result.push('\n');
+    }
+
+    // Part of speech (if available)
+    if !entry.part_of_speech.is_empty() {
+        result.push_str(&theme.pos.paint(&entry.part_of_speech).to_string());
+        result.push('\n');
+    }
+
+    // Format definitions, wrapped with continuation lines indented under the text
+    if !entry.senses.is_empty() {
+        format_senses(&mut result, entry, options);
+    } else if entry.definitions.len() == 1 {
+        push_wrapped(&mut result, "  ", "  ".to_string(), &entry.definitions[0], theme.gloss, options.width);
+    } else {
+        for (i, def) in entry.definitions.iter().enumerate() {
+            if i > 0 {
+                result.push('\n');
+            }
+            let number = format!("({})", i + 1);
+            if options.highlight_sense == Some(i + 1) {
+                let prefix = format!("{} {} ", theme.highlight.paint("▶"), theme.sense_number.paint(&number));
+                push_wrapped(&mut result, &format!("▶ {} ", number), prefix, def, theme.gloss.bold(), options.width);
+            } else {
+                let prefix = format!("  {} ", theme.sense_number.paint(&number));
+                push_wrapped(&mut result, &format!("  {} ", number), prefix, def, theme.gloss, options.width);
+            }
+        }
+    }
+
+    // Example sentences, each followed by its translation
+    if !entry.example_sentences.is_empty() {
+        result.push_str(&format!("\n  {}", theme.label.paint("例文:")));
+        for example in &entry.example_sentences {
+            result.push('\n');
+            push_wrapped(&mut result, "    ", "    ".to_string(), &example.text, theme.gloss, options.width);
+            if !example.translation.is_empty() {
+                result.push('\n');
+                push_wrapped(&mut result, "    ", "    ".to_string(), &example.translation, theme.example, options.width);
+            }
+        }
+    }
+
+    // Add numbered references, or plain synonyms for entries recorded without them
+    if !entry.references.is_empty() {
+        result.push_str(&format_references(entry, first_reference, options.width));
+    } else if !entry.synonyms.is_empty() {
+        result.push('\n');
+        let prefix = format!("  {} ", theme.label.paint("類語:"));
+        push_wrapped(&mut result, "  類語: ", prefix, &entry.synonyms.join(", "), theme.gloss, options.width);
+    }
+
+    // Unparsed entries have nothing below the headword
+    result.trim_end().to_string()
+}
+
+/// `label: value` pairs from `DictionaryEntry::metadata` on one line
+pub fn metadata_line(metadata: &[(&str, String)]) -> String {
+    metadata.iter()
+        .map(|(label, value)| format!("{}: {}", label, value))
+        .collect::<Vec<_>>()
+        .join("  ")
+}
+
+/// Senses as a tree: numbered senses, sub-senses indented under their text, examples below each
+fn format_senses(result: &mut String, entry: &DictionaryEntry, options: &FormatOptions) {
+    let theme = theme::current();
+    if let [sense] = entry.senses.as_slice() {
+        push_sense(result, sense, "  ", "  ".to_string(), theme.gloss, options.width);
+        return;
+    }
+
+    for (i, sense) in entry.senses.iter().enumerate() {
+        if i > 0 {
+            result.push('\n');
+        }
+        let number = format!("({})", i + 1);
+        if options.highlight_sense == Some(i + 1) {
+            let prefix = format!("{} {} ", theme.highlight.paint("▶"), theme.sense_number.paint(&number));
+            push_sense(result, sense, &format!("▶ {} ", number), prefix, theme.gloss.bold(), options.width);
+        } else {
+            let prefix = format!("  {} ", theme.sense_number.paint(&number));
+            push_sense(result, sense, &format!("  {} ", number), prefix, theme.gloss, options.width);
+        }
+    }
+}
+
+fn push_sense(result: &mut String, sense: &Sense, plain_prefix: &str, prefix: String, style: Style, width: Option<usize>) {
+    let theme = theme::current();
+    let indent = " ".repeat(wrap::width(plain_prefix));
+    let mut prefix = Some(prefix);
+
+    if !sense.text.is_empty() {
+        // A changed part of speech leads the text, which wraps under it
+        let (plain_prefix, line_prefix) = match sense.part_of_speech.as_str() {
+            "" => (plain_prefix.to_string(), prefix.take().unwrap_or_default()),
+            pos => (
+                format!("{}{} ", plain_prefix, pos),
+                format!("{}{} ", prefix.take().unwrap_or_default(), theme.pos.paint(pos)),
+            ),
+        };
+        push_wrapped(result, &plain_prefix, line_prefix, &sense.text, style, width);
+    }
+    if !sense.examples.is_empty() {
+        if prefix.is_none() {
+            result.push('\n');
+        }
+        push_wrapped(result, &indent, prefix.take().unwrap_or_else(|| indent.clone()), &sense.examples.concat(), theme.example, width);
+    }
+
+    for subsense in &sense.subsenses {
+        // A sense that is only a list of sub-senses starts the first one on its own line
+        let line_prefix = match prefix.take() {
+            Some(prefix) => prefix,
+            None => {
+                result.push('\n');
+                indent.clone()
+            }
+        };
+        let label = format!("{} ", subsense.label);
+        push_sense(
+            result,
+            subsense,
+            &format!("{}{}", indent, label),
+            format!("{}{}", line_prefix, theme.sense_number.paint(&label)),
+            theme.gloss,
+            width,
+        );
+    }
+}
+
+fn format_references(entry: &DictionaryEntry, first_reference: usize, width: Option<usize>) -> String {
+    let theme = theme::current();
+    // Each reference is kept whole when wrapping, as (plain, colored) text
+    let mut synonyms = Vec::new();
+    let mut others = Vec::new();
+
+    for (i, reference) in entry.references.iter().enumerate() {
+        let number = format!("[{}]", first_reference + i);
+        let mut target = reference.term.clone();
+        if let Some(sense) = reference.sense {
+            target.push_str(&format!(" ({})", sense));
+        }
+
+        let colored = format!("{}{}", theme.reference_number.paint(&number), theme.gloss.paint(&target));
+        match reference.kind {
+            ReferenceKind::Synonym => synonyms.push((format!("{}{}", number, target), colored)),
+            kind => others.push((
+                format!("{}{} {}", number, target, kind.label()),
+                format!("{} {}", colored, theme.muted.paint(kind.label())),
+            )),
+        }
+    }
+
+    let mut result = String::new();
+    for (label, items) in [("類語:", synonyms), ("参照:", others)] {
+        if items.is_empty() {
+            continue;
+        }
+        let indent = wrap::width(label) + 3;
+        let mut line_width = indent;
+        result.push_str(&format!("\n  {} ", theme.label.paint(label)));
+        for (i, (plain, colored)) in items.iter().enumerate() {
+            let item_width = wrap::width(plain) + usize::from(i + 1 < items.len());
+            if i > 0 && width.is_some_and(|width| line_width + 1 + item_width > width) {
+                result.push_str(&format!("\n{}", " ".repeat(indent)));
+                line_width = indent;
+            } else if i > 0 {
+                result.push(' ');
+                line_width += 1;
+            }
+            result.push_str(colored);
+            if i + 1 < items.len() {
+                result.push(',');
+            }
+            line_width += item_width;
+        }
+    }
+    result
+}
+
+/// Add `text` after a prefix (given plain, to measure it, and colored), wrapped to `width`
+fn push_wrapped(result: &mut String, plain_prefix: &str, prefix: String, text: &str, style: Style, width: Option<usize>) {
+    let indent = " ".repeat(wrap::width(plain_prefix));
+    result.push_str(&prefix);
+    for (i, line) in wrap::wrap_hanging(plain_prefix, text, width).iter().enumerate() {
+        if i > 0 {
+            result.push('\n');
+            result.push_str(&indent);
+        }
+        result.push_str(&style.paint(line).to_string());
+    }
+}
+
+fn format_headword(entry: &DictionaryEntry, options: &FormatOptions) -> Vec<String> {
+    let Some(style) = options.furigana else {
+        return vec![entry.word_reading.clone()];
+    };
+
+    let segments = furigana::align(&entry.headword(), &entry.reading());
+    match style {
+        FuriganaStyle::Inline => vec![furigana::to_inline(&segments)],
+        FuriganaStyle::Ruby => {
+            let (readings, text) = furigana::to_ruby(&segments);
+            if readings.is_empty() {
+                vec![text]
+            } else {
+                vec![readings, text]
+            }
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use std::str::FromStr;
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FuriganaStyle {
+    /// Readings in brackets after the kanji they belong to, e.g. `入[はい]る`
+    Inline,
+    /// Readings on a line above the kanji they belong to
+    Ruby,
+}
+
+impl FuriganaStyle {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FuriganaStyle::Inline => "inline",
+            FuriganaStyle::Ruby => "ruby",
+        }
+    }
+}
+
+impl FromStr for FuriganaStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "inline" => Ok(FuriganaStyle::Inline),
+            "ruby" => Ok(FuriganaStyle::Ruby),
+            other => Err(format!("unknown furigana style: {} (expected inline or ruby)", other)),
+        }
+    }
+}
+
+/// A run of the written form, with its reading if it contains kanji
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub text: String,
+    pub reading: Option<String>,
+}
+
+/// Align a written form to its kana reading, e.g. 入る/はいる -> [入(はい), る]
+///
+/// Kana in the written form are matched against the reading and the kanji runs between
+/// them take whatever is left. If no alignment exists the whole word gets the whole reading.
+pub fn align(word: &str, reading: &str) -> Vec<Segment> {
+    let runs = split_runs(word);
+
+    if reading.is_empty() || runs.iter().all(|(_, is_kana)| *is_kana) {
+        return vec![Segment { text: word.to_string(), reading: None }];
+    }
+
+    let reading_chars: Vec<char> = reading.chars().collect();
+    let mut segments = Vec::new();
+
+    if match_runs(&runs, &reading_chars, &mut segments) {
+        segments
+    } else {
+        vec![Segment { text: word.to_string(), reading: Some(reading.to_string()) }]
+    }
+}
+
+/// Bracketed furigana, e.g. `入[はい]る`
+pub fn to_inline(segments: &[Segment]) -> String {
+    segments.iter()
+        .map(|segment| match &segment.reading {
+            Some(reading) => format!("{}[{}]", segment.text, reading),
+            None => segment.text.clone(),
+        })
+        .collect()
+}
+
+/// Two-line ruby rendering, returned as (reading line, text line) with each reading
+/// centered over its kanji
+pub fn to_ruby(segments: &[Segment]) -> (String, String) {
+    let mut top = String::new();
+    let mut bottom = String::new();
+
+    for segment in segments {
+        let reading = segment.reading.as_deref().unwrap_or("");
+        let cell = segment.text.width().max(reading.width());
+        top.push_str(&center(reading, cell));
+        bottom.push_str(&center(&segment.text, cell));
+    }
+
+    (top.trim_end().to_string(), bottom.trim_end().to_string())
+}
+
+fn center(text: &str, cell: usize) -> String {
+    let padding = cell - text.width();
+    let left = padding / 2;
+    format!("{}{}{}", " ".repeat(left), text, " ".repeat(padding - left))
+}
+
+fn match_runs(runs: &[(String, bool)], reading: &[char], segments: &mut Vec<Segment>) -> bool {
+    let Some(((text, is_kana), rest)) = runs.split_first() else {
+        return reading.is_empty();
+    };
+
+    if *is_kana {
+        let len = text.chars().count();
+        if reading.len() < len || !same_kana(text, &reading[..len]) {
+            return false;
+        }
+        segments.push(Segment { text: text.clone(), reading: None });
+        if match_runs(rest, &reading[len..], segments) {
+            return true;
+        }
+        segments.pop();
+        false
+    } else {
+        // A kanji run reads as at least one kana; prefer the shortest reading that lets the rest match
+        for len in 1..=reading.len() {
+            let run_reading: String = reading[..len].iter().collect();
+            segments.push(Segment { text: text.clone(), reading: Some(run_reading) });
+            if match_runs(rest, &reading[len..], segments) {
+                return true;
+            }
+            segments.pop();
+        }
+        false
+    }
+}
+
+/// Split a word into alternating runs of kana and non-kana characters
+fn split_runs(word: &str) -> Vec<(String, bool)> {
+    let mut runs: Vec<(String, bool)> = Vec::new();
+
+    for ch in word.chars() {
+        let kana = is_kana(ch);
+        match runs.last_mut() {
+            Some((run, run_is_kana)) if *run_is_kana == kana => run.push(ch),
+            _ => runs.push((ch.to_string(), kana)),
+        }
+    }
+
+    runs
+}
+
+pub fn is_kana(ch: char) -> bool {
+    matches!(ch, '\u{3041}'..='\u{3096}' | '\u{309D}'..='\u{309E}' | '\u{30A1}'..='\u{30FA}' | 'ー')
+}
+
+/// Convert katakana to hiragana, leaving everything else untouched
+pub fn to_hiragana(text: &str) -> String {
+    text.chars()
+        .map(|ch| match ch {
+            '\u{30A1}'..='\u{30F6}' => char::from_u32(ch as u32 - 0x60).unwrap_or(ch),
+            _ => ch,
+        })
+        .collect()
+}
+
+fn same_kana(text: &str, reading: &[char]) -> bool {
+    let reading: String = reading.iter().collect();
+    to_hiragana(text) == to_hiragana(&reading)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, reading: Option<&str>) -> Segment {
+        Segment { text: text.to_string(), reading: reading.map(str::to_string) }
+    }
+
+    #[test]
+    fn aligns_okurigana() {
+        assert_eq!(align("食べる", "たべる"), [segment("食", Some("た")), segment("べる", None)]);
+    }
+
+    #[test]
+    fn leaves_kana_words_alone() {
+        assert_eq!(align("ありがとう", "ありがとう"), [segment("ありがとう", None)]);
+        assert_eq!(align("テレビ", "てれび"), [segment("テレビ", None)]);
+    }
+
+    #[test]
+    fn aligns_kanji_kana_kanji() {
+        assert_eq!(
+            align("取り扱い", "とりあつかい"),
+            [segment("取", Some("と")), segment("り", None), segment("扱", Some("あつか")), segment("い", None)],
+        );
+    }
+
+    #[test]
+    fn aligns_a_kana_prefix() {
+        assert_eq!(align("お茶", "おちゃ"), [segment("お", None), segment("茶", Some("ちゃ"))]);
+    }
+
+    #[test]
+    fn falls_back_to_the_whole_word_on_a_mismatch() {
+        assert_eq!(align("食べる", "のむ"), [segment("食べる", Some("のむ"))]);
+        assert_eq!(align("食べる", ""), [segment("食べる", None)]);
+    }
+
+    #[test]
+    fn renders_inline_readings() {
+        assert_eq!(to_inline(&align("入る", "はいる")), "入[はい]る");
+    }
+
+    #[test]
+    fn centers_ruby_over_its_kanji() {
+        // はい is twice as wide as 入, so the kanji is padded on both sides
+        let (top, bottom) = to_ruby(&align("入る", "はいる"));
+        assert_eq!(top, "はい");
+        assert_eq!(bottom, " 入 る");
+
+        let (top, bottom) = to_ruby(&align("食べる", "たべる"));
+        assert_eq!(top, "た");
+        assert_eq!(bottom, "食べる");
+    }
+
+    #[test]
+    fn centers_short_readings_under_wide_runs() {
+        let (top, bottom) = to_ruby(&[segment("日本", Some("に"))]);
+        assert_eq!(top, " に");
+        assert_eq!(bottom, "日本");
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{Dictionary, DictionaryEntry, DictionaryResult};
+use crate::format::{FormatOptions, format_entries};
+use crate::http;
+use crate::sense::{self, Sense};
+use scraper::{ElementRef, Html, Selector};
+
+pub const SOURCE: &str = "goo";
+
+/// goo国語辞書 (dictionary.goo.ne.jp)
+pub struct Goo;
+
+impl Dictionary for Goo {
+    fn name(&self) -> &'static str {
+        SOURCE
+    }
+
+    fn lookup(&self, term: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
+        lookup_goo(term)
+    }
+}
+
+pub fn search_goo(term: &str) -> DictionaryResult<String> {
+    let entries = lookup_goo(term)?;
+    Ok(format_entries(&entries, &FormatOptions::default()))
+}
+
+pub fn lookup_goo(term: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
+    let url = format!("https://dictionary.goo.ne.jp/word/{}/", term);
+
+    let Some(html) = http::get_page(SOURCE, &url)? else {
+        return Ok(Vec::new());
+    };
+
+    let document = Html::parse_document(&html);
+
+    Ok(parse_goo_entries(&document))
+}
+
+/// Each headword is a `div.basic_title` followed by a `div.meaning_area` with its senses
+fn parse_goo_entries(document: &Html) -> Vec<DictionaryEntry> {
+    let title_selector = Selector::parse("div.basic_title h1").unwrap();
+    let meaning_selector = Selector::parse("div.meaning_area").unwrap();
+
+    let titles: Vec<String> = document.select(&title_selector)
+        .map(|title| headword_from_title(&title.text().collect::<String>()))
+        .collect();
+
+    document.select(&meaning_selector)
+        .enumerate()
+        .filter_map(|(i, meaning)| {
+            let word_reading = titles.get(i).or(titles.first())?.clone();
+            parse_meaning(&meaning, word_reading)
+        })
+        .collect()
+}
+
+/// "せい‐かい【正解】 の意味" → "せいかい【正解】"
+fn headword_from_title(title: &str) -> String {
+    let title = title.trim();
+    let title = title.strip_suffix("の意味").or_else(|| title.strip_suffix("の解説")).unwrap_or(title);
+    title.trim().replace('‐', "")
+}
+
+fn parse_meaning(meaning: &ElementRef, word_reading: String) -> Option<DictionaryEntry> {
+    let hinshi_selector = Selector::parse(".hinshi").unwrap();
+    let item_selector = Selector::parse("ol.meaning li").unwrap();
+    let text_selector = Selector::parse(".text").unwrap();
+
+    let mut part_of_speech = meaning.select(&hinshi_selector)
+        .map(|span| span.text().collect::<String>().trim().to_string())
+        .find(|text| !text.is_empty())
+        .unwrap_or_default();
+
+    // Numbered senses are list items; single-sense words have a single text block
+    let mut senses: Vec<Sense> = meaning.select(&item_selector)
+        .enumerate()
+        .map(|(i, item)| {
+            let mut sense = Sense::new(&(i + 1).to_string());
+            sense.text = item.text().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ");
+            sense.finish();
+            sense
+        })
+        .filter(|sense| !sense.text.is_empty())
+        .collect();
+
+    if senses.is_empty() {
+        let text = meaning.select(&text_selector).next()?.text().collect::<String>();
+        let (leading_pos, description) = sense::split_description(&text);
+        senses = description;
+        if part_of_speech.is_empty() {
+            part_of_speech = leading_pos;
+        }
+    }
+
+    let definitions: Vec<String> = senses.iter().map(Sense::plain).filter(|text| !text.is_empty()).collect();
+    if definitions.is_empty() {
+        return None;
+    }
+
+    let entry = DictionaryEntry::new(word_reading, part_of_speech, definitions)
+        .with_senses(senses)
+        .with_source(SOURCE);
+    Some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_text_before_numbered_senses() {
+        let document = Html::parse_document(
+            "<div class=\"basic_title\"><h1>せい‐かい【正解】 の意味</h1></div>\
+            <div class=\"meaning_area\"><div class=\"contents\"><div class=\"text\">\
+            ［名］(スル)《「正しく解く」の意》１ 正しく解釈すること。「問題の意図を―する」２ 正しい答え。\
+            </div></div></div>",
+        );
+        let entries = parse_goo_entries(&document);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].word_reading, "せいかい【正解】");
+        assert_eq!(entries[0].part_of_speech, "［名］(スル)");
+        assert_eq!(entries[0].definitions, ["《「正しく解く」の意》", "正しく解釈すること。「問題の意図を―する」", "正しい答え。"]);
+        assert_eq!(entries[0].senses[1].examples, ["「問題の意図を―する」"]);
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{DictionaryEntry, DictionaryResult, data_dir};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One successful lookup, with the entries it returned so it can be shown again offline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    pub id: u64,
+    pub term: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub backend: String,
+    #[serde(default)]
+    pub starred: bool,
+    pub entries: Vec<DictionaryEntry>,
+}
+
+impl HistoryRecord {
+    pub fn matches(&self, query: &str) -> bool {
+        self.term.contains(query)
+            || self.entries.iter().any(|entry| {
+                entry.word_reading.contains(query)
+                    || entry.definitions.iter().any(|def| def.contains(query))
+            })
+    }
+}
+
+/// Lookup history stored as JSON lines, one record per line
+pub struct History {
+    path: PathBuf,
+    records: Vec<HistoryRecord>,
+    /// Size and modification time of the file when it was last read or written, to notice
+    /// other processes' changes
+    stamp: (u64, Option<SystemTime>),
+}
+
+impl History {
+    /// `history.jsonl` in the data directory
+    pub fn default_path() -> Option<PathBuf> {
+        data_dir().map(|dir| dir.join("history.jsonl"))
+    }
+
+    pub fn open(path: &Path) -> DictionaryResult<Self> {
+        let mut records = Vec::new();
+
+        if path.exists() {
+            let contents = fs::read_to_string(path)?;
+            for (number, line) in contents.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let record = serde_json::from_str(line)
+                    .map_err(|e| format!("{} line {}: {}", path.display(), number + 1, e))?;
+                records.push(record);
+            }
+        }
+
+        Ok(Self { path: path.to_path_buf(), records, stamp: file_stamp(path) })
+    }
+
+    /// Reread the file if another process has changed it since, so new ids don't collide
+    fn refresh(&mut self) -> DictionaryResult<()> {
+        if file_stamp(&self.path) != self.stamp {
+            *self = Self::open(&self.path)?;
+        }
+        Ok(())
+    }
+
+    pub fn records(&self) -> &[HistoryRecord] {
+        &self.records
+    }
+
+    pub fn get(&self, id: u64) -> Option<&HistoryRecord> {
+        self.records.iter().find(|record| record.id == id)
+    }
+
+    pub fn search(&self, query: &str) -> Vec<&HistoryRecord> {
+        self.records.iter().filter(|record| record.matches(query)).collect()
+    }
+
+    /// Append a lookup to the history file
+    pub fn add(&mut self, term: &str, backend: &str, entries: &[DictionaryEntry]) -> DictionaryResult<&HistoryRecord> {
+        self.refresh()?;
+        let record = HistoryRecord {
+            id: self.records.last().map_or(1, |record| record.id + 1),
+            term: term.to_string(),
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0),
+            backend: backend.to_string(),
+            starred: false,
+            entries: entries.to_vec(),
+        };
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&record)?)?;
+        self.stamp = file_stamp(&self.path);
+
+        self.records.push(record);
+        Ok(self.records.last().unwrap())
+    }
+
+    pub fn set_starred(&mut self, id: u64, starred: bool) -> DictionaryResult<()> {
+        self.refresh()?;
+        match self.records.iter_mut().find(|record| record.id == id) {
+            Some(record) => record.starred = starred,
+            None => return Err(format!("no history entry {}", id).into()),
+        }
+        self.save()
+    }
+
+    fn save(&mut self) -> DictionaryResult<()> {
+        let mut contents = String::new();
+        for record in &self.records {
+            contents.push_str(&serde_json::to_string(record)?);
+            contents.push('\n');
+        }
+
+        // Write to a temporary file first so an interrupted save can't truncate the history
+        let temp_path = self.path.with_extension("jsonl.tmp");
+        fs::write(&temp_path, contents)?;
+        fs::rename(&temp_path, &self.path)?;
+        self.stamp = file_stamp(&self.path);
+        Ok(())
+    }
+}
+
+/// Size and modification time, (0, None) for a file that doesn't exist yet
+fn file_stamp(path: &Path) -> (u64, Option<SystemTime>) {
+    match fs::metadata(path) {
+        Ok(metadata) => (metadata.len(), metadata.modified().ok()),
+        Err(_) => (0, None),
+    }
+}
+
+/// Format a Unix timestamp as `YYYY-MM-DD HH:MM` (UTC)
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let seconds_of_day = timestamp % 86_400;
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_stay_unique_when_another_process_appends() {
+        let path = std::env::temp_dir().join(format!("jisho-history-test-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut this = History::open(&path).unwrap();
+        this.add("正解", "weblio", &[]).unwrap();
+        let mut other = History::open(&path).unwrap();
+        other.add("思う", "wwwjdic", &[]).unwrap();
+        let id = this.add("行く", "wwwjdic", &[]).unwrap().id;
+
+        let ids: Vec<u64> = History::open(&path).unwrap().records().iter().map(|record| record.id).collect();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(id, 3);
+        assert_eq!(ids, [1, 2, 3]);
+    }
+}
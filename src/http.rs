@@ -0,0 +1,211 @@
+use crate::DictionaryResult;
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, Response};
+use reqwest::header::RETRY_AFTER;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+static CLIENT: OnceLock<Client> = OnceLock::new();
+static SETTINGS: OnceLock<HttpSettings> = OnceLock::new();
+
+/// Least time between two requests to the same backend in milliseconds, 0 for no limit
+static MIN_INTERVAL_MS: AtomicU64 = AtomicU64::new(0);
+/// When each backend may be sent its next request
+static NEXT_REQUEST: OnceLock<Mutex<HashMap<String, Instant>>> = OnceLock::new();
+
+/// Retries after a transient failure unless the settings say otherwise
+pub const DEFAULT_RETRIES: u32 = 2;
+/// Sent as the User-Agent header unless the settings give another
+pub const DEFAULT_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+/// Most retries the settings may ask for
+pub const MAX_RETRIES: u32 = 10;
+/// Wait before the first retry, doubled for each one after it
+const BACKOFF: Duration = Duration::from_millis(500);
+/// Longest wait between retries when the server doesn't say
+const MAX_BACKOFF: Duration = Duration::from_secs(8);
+/// Longest a server's Retry-After is honored for
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub struct HttpSettings {
+    pub timeout: Duration,
+    /// Timeouts for particular backends by name, overriding `timeout`
+    pub backend_timeouts: HashMap<String, Duration>,
+    /// Sent as the User-Agent header, `DEFAULT_USER_AGENT` if None
+    pub user_agent: Option<String>,
+    /// Extra attempts after timeouts, connection errors, 429 and 5xx responses
+    pub retries: u32,
+}
+
+/// Why a request failed, with the status code mapped to what it means for a lookup
+#[derive(Debug)]
+pub enum HttpError {
+    /// 404, which for dictionary pages usually means there is no such entry
+    NotFound { url: String },
+    /// 429, still after retrying
+    RateLimited { url: String, retry_after: Option<Duration> },
+    /// 5xx, e.g. a 503 page while the site is down
+    Server { status: StatusCode, url: String, retry_after: Option<Duration> },
+    /// Any other unsuccessful status
+    Status { status: StatusCode, url: String },
+    Timeout { url: String },
+    /// Connection failures and other errors from the HTTP client
+    Request(reqwest::Error),
+}
+
+impl HttpError {
+    /// Whether trying again later might work
+    pub fn is_transient(&self) -> bool {
+        match self {
+            HttpError::RateLimited { .. } | HttpError::Server { .. } | HttpError::Timeout { .. } => true,
+            HttpError::Request(e) => e.is_connect(),
+            HttpError::NotFound { .. } | HttpError::Status { .. } => false,
+        }
+    }
+
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            HttpError::RateLimited { retry_after, .. } | HttpError::Server { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HttpError::NotFound { url } => write!(f, "not found: {}", url),
+            HttpError::RateLimited { url, .. } => write!(f, "rate limited by the server: {}", url),
+            HttpError::Server { status, url, .. } => write!(f, "server error {}: {}", status, url),
+            HttpError::Status { status, url } => write!(f, "unexpected status {}: {}", status, url),
+            HttpError::Timeout { url } => write!(f, "timed out: {}", url),
+            HttpError::Request(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for HttpError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HttpError::Request(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Settings for the shared client. Only the first call has an effect, and only
+/// if it comes before the first request.
+pub fn configure(settings: HttpSettings) {
+    let _ = SETTINGS.set(settings);
+}
+
+/// Send each backend at most `requests_per_second` (None: no limit), so that batch
+/// lookups don't hammer the sites. Can be changed at any time.
+pub fn limit_rate(requests_per_second: Option<f64>) {
+    let millis = requests_per_second
+        .filter(|&rate| rate > 0.0)
+        .map(|rate| (1000.0 / rate) as u64)
+        .unwrap_or(0);
+    MIN_INTERVAL_MS.store(millis, Ordering::Relaxed);
+}
+
+/// Shared HTTP client, so repeated lookups reuse connections instead of redoing TLS each time
+pub fn client() -> &'static Client {
+    CLIENT.get_or_init(|| {
+        let mut builder = Client::builder().user_agent(DEFAULT_USER_AGENT);
+        if let Some(settings) = SETTINGS.get() {
+            builder = builder.timeout(settings.timeout);
+            if let Some(user_agent) = &settings.user_agent {
+                builder = builder.user_agent(user_agent);
+            }
+        }
+        builder.build().unwrap_or_else(|_| Client::new())
+    })
+}
+
+/// Body of a successful response from `backend`, retrying transient failures with backoff
+pub fn get_text(backend: &str, url: &str) -> DictionaryResult<String> {
+    let retries = SETTINGS.get().map(|settings| settings.retries).unwrap_or(DEFAULT_RETRIES);
+    let mut attempt = 0;
+    loop {
+        wait_for_turn(backend);
+        match fetch(backend, url) {
+            Ok(text) => return Ok(text),
+            Err(e) if e.is_transient() && attempt < retries => {
+                let backoff = BACKOFF.saturating_mul(2u32.saturating_pow(attempt)).min(MAX_BACKOFF);
+                let delay = e.retry_after().unwrap_or(backoff);
+                thread::sleep(delay.min(MAX_RETRY_DELAY));
+                attempt += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Like `get_text`, but None for a 404 instead of an error
+pub fn get_page(backend: &str, url: &str) -> DictionaryResult<Option<String>> {
+    match get_text(backend, url) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if matches!(e.downcast_ref::<HttpError>(), Some(HttpError::NotFound { .. })) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn fetch(backend: &str, url: &str) -> Result<String, HttpError> {
+    let mut request = client().get(url);
+    if let Some(timeout) = SETTINGS.get().and_then(|settings| settings.backend_timeouts.get(backend)) {
+        request = request.timeout(*timeout);
+    }
+
+    let response = request.send().map_err(|e| request_error(e, url))?;
+    let status = response.status();
+    if !status.is_success() {
+        let url = url.to_string();
+        return Err(match status {
+            StatusCode::NOT_FOUND => HttpError::NotFound { url },
+            StatusCode::TOO_MANY_REQUESTS => HttpError::RateLimited { url, retry_after: retry_after(&response) },
+            status if status.is_server_error() => HttpError::Server { status, url, retry_after: retry_after(&response) },
+            status => HttpError::Status { status, url },
+        });
+    }
+    response.text().map_err(|e| request_error(e, url))
+}
+
+fn request_error(error: reqwest::Error, url: &str) -> HttpError {
+    if error.is_timeout() {
+        HttpError::Timeout { url: url.to_string() }
+    } else {
+        HttpError::Request(error)
+    }
+}
+
+/// Retry-After given in seconds (the HTTP date form is rare enough to ignore)
+fn retry_after(response: &Response) -> Option<Duration> {
+    let seconds = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim().parse().ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+/// Sleep until `backend` may be sent another request under the rate limit
+fn wait_for_turn(backend: &str) {
+    let interval = Duration::from_millis(MIN_INTERVAL_MS.load(Ordering::Relaxed));
+    if interval.is_zero() {
+        return;
+    }
+
+    let wait = {
+        let mut next_request = NEXT_REQUEST.get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let next = next_request.entry(backend.to_string()).or_insert(now);
+        let start = (*next).max(now);
+        *next = start + interval;
+        start - now
+    };
+    thread::sleep(wait);
+}
@@ -0,0 +1,264 @@
+use crate::{Dictionary, DictionaryEntry, DictionaryResult};
+use crate::format::{FormatOptions, format_entries};
+use crate::http;
+use crate::reference::{Reference, ReferenceKind};
+use crate::sense::Sense;
+use reqwest::Url;
+use serde::Deserialize;
+
+pub const SOURCE: &str = "jisho.org";
+
+const API_URL: &str = "https://jisho.org/api/v1/search/words";
+
+/// jisho.org's word search API (`-b jisho.org`; plain `jisho` still means wwwjdic)
+pub struct JishoOrg;
+
+impl Dictionary for JishoOrg {
+    fn name(&self) -> &'static str {
+        SOURCE
+    }
+
+    fn lookup(&self, term: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
+        lookup_jisho_org(term)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    #[serde(default)]
+    data: Vec<Word>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Word {
+    is_common: Option<bool>,
+    tags: Vec<String>,
+    jlpt: Vec<String>,
+    japanese: Vec<Japanese>,
+    senses: Vec<WordSense>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Japanese {
+    word: Option<String>,
+    reading: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct WordSense {
+    english_definitions: Vec<String>,
+    parts_of_speech: Vec<String>,
+    tags: Vec<String>,
+    info: Vec<String>,
+    see_also: Vec<String>,
+    antonyms: Vec<String>,
+}
+
+pub fn search_jisho_org(term: &str) -> DictionaryResult<String> {
+    let entries = lookup_jisho_org(term)?;
+    Ok(format_entries(&entries, &FormatOptions::default()))
+}
+
+pub fn lookup_jisho_org(term: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
+    let url = Url::parse_with_params(API_URL, &[("keyword", term)])?;
+
+    let json = http::get_text(SOURCE, url.as_str())?;
+
+    parse_response(&json)
+}
+
+fn parse_response(json: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
+    let response: SearchResponse = serde_json::from_str(json)
+        .map_err(|e| format!("unexpected response from jisho.org: {}", e))?;
+    Ok(response.data.iter().filter_map(word_entry).collect())
+}
+
+fn word_entry(word: &Word) -> Option<DictionaryEntry> {
+    // EDICT style "word;word [reading;reading]", which `headword` and `reading` understand
+    let forms = unique(word.japanese.iter().filter_map(|japanese| japanese.word.clone()));
+    let readings = unique(word.japanese.iter().filter_map(|japanese| japanese.reading.clone()));
+    let word_reading = match (forms.is_empty(), readings.is_empty()) {
+        (false, false) => format!("{} [{}]", forms.join(";"), readings.join(";")),
+        (false, true) => forms.join(";"),
+        (true, false) => readings.join(";"),
+        (true, true) => return None,
+    };
+
+    let senses: Vec<&WordSense> = word.senses.iter()
+        .filter(|sense| !sense.english_definitions.is_empty())
+        .collect();
+    if senses.is_empty() {
+        return None;
+    }
+
+    // The entry's part of speech is the first sense's; later senses note where it changes
+    let part_of_speech = senses[0].parts_of_speech.join(", ");
+    let mut previous_pos = part_of_speech.clone();
+    let word_senses: Vec<Sense> = senses.iter()
+        .enumerate()
+        .map(|(i, sense)| {
+            let pos = sense.parts_of_speech.join(", ");
+            let mut word_sense = Sense::new(&(i + 1).to_string());
+            word_sense.text = sense_definition(sense);
+            if !pos.is_empty() && pos != previous_pos {
+                word_sense.part_of_speech = pos.clone();
+                previous_pos = pos;
+            }
+            word_sense
+        })
+        .collect();
+    let definitions: Vec<String> = word_senses.iter().map(Sense::plain).collect();
+
+    let mut references = Vec::new();
+    for sense in &senses {
+        references.extend(sense.see_also.iter().filter_map(|target| parse_reference(ReferenceKind::See, target)));
+        references.extend(sense.antonyms.iter().filter_map(|target| parse_reference(ReferenceKind::Antonym, target)));
+    }
+
+    let entry = DictionaryEntry::new(word_reading, part_of_speech, definitions)
+        .with_senses(word_senses)
+        .with_references(references)
+        .with_tags(word_tags(word))
+        .with_source(SOURCE);
+    Some(entry)
+}
+
+/// "to go; to move" followed by sense tags and notes, e.g. "(Usually written using kana alone)"
+fn sense_definition(sense: &WordSense) -> String {
+    let mut definition = sense.english_definitions.join("; ");
+    let notes: Vec<&str> = sense.tags.iter().chain(&sense.info).map(String::as_str).collect();
+    if !notes.is_empty() {
+        definition.push_str(&format!(" ({})", notes.join(", ")));
+    }
+    definition
+}
+
+/// "common", JLPT levels as "JLPT N5" and the word's own tags
+fn word_tags(word: &Word) -> Vec<String> {
+    let mut tags = Vec::new();
+    if word.is_common == Some(true) {
+        tags.push("common".to_string());
+    }
+    tags.extend(word.jlpt.iter().map(|level| level.replace("jlpt-n", "JLPT N")));
+    tags.extend(word.tags.iter().cloned());
+    tags
+}
+
+/// see_also and antonyms targets like "行く 1" (term, then an optional sense number)
+fn parse_reference(kind: ReferenceKind, target: &str) -> Option<Reference> {
+    let mut parts = target.split_whitespace();
+    let mut reference = Reference::new(kind, parts.next()?.to_string());
+    reference.sense = parts.next().and_then(|sense| sense.parse().ok());
+    Some(reference)
+}
+
+fn unique(values: impl Iterator<Item = String>) -> Vec<String> {
+    let mut unique = Vec::new();
+    for value in values {
+        if !unique.contains(&value) {
+            unique.push(value);
+        }
+    }
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &str = r#"{
+        "meta": {"status": 200},
+        "data": [
+            {
+                "slug": "勉強",
+                "is_common": true,
+                "tags": ["wanikani5"],
+                "jlpt": ["jlpt-n5"],
+                "japanese": [{"word": "勉強", "reading": "べんきょう"}],
+                "senses": [
+                    {"english_definitions": ["study"], "parts_of_speech": ["Noun"], "tags": [], "info": [], "see_also": [], "antonyms": []},
+                    {"english_definitions": ["to study"], "parts_of_speech": ["Suru verb"], "tags": [], "info": [], "see_also": ["学習 1"], "antonyms": []},
+                    {"english_definitions": ["diligence", "working hard"], "parts_of_speech": ["Suru verb"], "tags": ["Archaism"], "info": ["formerly"], "see_also": [], "antonyms": ["怠ける"]},
+                    {"english_definitions": ["Benkyō"], "parts_of_speech": ["Wikipedia definition"], "tags": [], "info": [], "see_also": [], "antonyms": []}
+                ]
+            },
+            {
+                "slug": "ありがとう",
+                "is_common": false,
+                "tags": [],
+                "jlpt": [],
+                "japanese": [{"reading": "ありがとう"}],
+                "senses": [{"english_definitions": ["thank you"], "parts_of_speech": ["Expressions (phrases, clauses, etc.)"]}]
+            },
+            {
+                "japanese": [{"word": "行く", "reading": "いく"}, {"word": "逝く", "reading": "いく"}, {"word": "行く", "reading": "ゆく"}],
+                "senses": [{"english_definitions": ["to go"], "parts_of_speech": ["Godan verb"]}]
+            },
+            {
+                "japanese": [{"word": "空"}],
+                "senses": [{"english_definitions": [], "parts_of_speech": []}]
+            }
+        ]
+    }"#;
+
+    fn entries() -> Vec<DictionaryEntry> {
+        parse_response(RESPONSE).unwrap()
+    }
+
+    #[test]
+    fn maps_forms_readings_and_tags() {
+        let entries = entries();
+        // Words without definitions are dropped
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].word_reading, "勉強 [べんきょう]");
+        assert_eq!(entries[0].tags, ["common", "JLPT N5", "wanikani5"]);
+        assert_eq!(entries[0].source, SOURCE);
+    }
+
+    #[test]
+    fn maps_kana_only_words() {
+        let entry = &entries()[1];
+        assert_eq!(entry.word_reading, "ありがとう");
+        assert_eq!(entry.headword(), "ありがとう");
+        assert!(entry.tags.is_empty());
+    }
+
+    #[test]
+    fn joins_several_forms_and_readings() {
+        let entry = &entries()[2];
+        assert_eq!(entry.word_reading, "行く;逝く [いく;ゆく]");
+        assert_eq!(entry.headword(), "行く");
+        assert_eq!(entry.reading(), "いく");
+    }
+
+    #[test]
+    fn keeps_the_part_of_speech_per_sense() {
+        let entry = &entries()[0];
+        assert_eq!(entry.part_of_speech, "Noun");
+        let pos: Vec<&str> = entry.senses.iter().map(|sense| sense.part_of_speech.as_str()).collect();
+        assert_eq!(pos, ["", "Suru verb", "", "Wikipedia definition"]);
+        assert_eq!(entry.definitions[1], "(Suru verb) to study");
+        assert_eq!(entry.definitions[2], "diligence; working hard (Archaism, formerly)");
+    }
+
+    #[test]
+    fn parses_see_also_and_antonyms() {
+        let entry = &entries()[0];
+        assert_eq!(entry.references.len(), 2);
+        assert_eq!(entry.references[0].kind, ReferenceKind::See);
+        assert_eq!(entry.references[0].term, "学習");
+        assert_eq!(entry.references[0].sense, Some(1));
+        assert_eq!(entry.references[1].kind, ReferenceKind::Antonym);
+        assert_eq!(entry.references[1].term, "怠ける");
+        assert_eq!(entry.references[1].sense, None);
+    }
+
+    #[test]
+    fn rejects_unexpected_responses() {
+        assert!(parse_response("<html>Service Unavailable</html>").is_err());
+        assert!(parse_response("{}").unwrap().is_empty());
+    }
+}
@@ -1,43 +1,83 @@
-use crate::DictionaryResult;
-use reqwest::blocking::get;
+use crate::{Dictionary, DictionaryEntry, DictionaryResult, debug};
+use crate::format::{FormatOptions, format_entries};
+use crate::http;
+use crate::reference::parse_edict_references;
 use scraper::{Html, Selector};
-use colored::*;
+
+pub const SOURCE: &str = "wwwjdic";
+
+pub struct Wwwjdic;
+
+impl Dictionary for Wwwjdic {
+    fn name(&self) -> &'static str {
+        SOURCE
+    }
+
+    fn lookup(&self, term: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
+        lookup_jisho(term)
+    }
+}
 
 pub fn search_jisho(term: &str) -> DictionaryResult<String> {
+    let entries = lookup_jisho(term)?;
+    Ok(format_entries(&entries, &FormatOptions::default()))
+}
+
+pub fn lookup_jisho(term: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
     let url = format!("https://www.edrdg.org/cgi-bin/wwwjdic/wwwjdic?1ZUJ{}", term);
     
-    let response = get(&url)?;
-    let html = response.text()?;
+    let html = http::get_text(SOURCE, &url)?;
     
-    let document = Html::parse_document(&html);
+    parse_response(&html)
+}
+
+/// EDICT lines from the `pre` blocks of a wwwjdic result page. Anything else in them
+/// (headers, notices, error text) is skipped and shown in debug mode.
+fn parse_response(html: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
+    let document = Html::parse_document(html);
     let selector = Selector::parse("pre").unwrap();
     
-    let mut formatted_entries = Vec::new();
+    let mut entries = Vec::new();
+    let mut skipped = 0;
 
     for element in document.select(&selector) {
         let text = element.text().collect::<String>();
-        if !text.trim().is_empty() {
-            let lines: Vec<&str> = text.trim().lines().collect();
-            for line in lines {
-                if !line.trim().is_empty() {
-                    formatted_entries.push(format_entry(line));
-                }
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if is_edict_line(line) {
+                entries.push(parse_entry(line).with_source(SOURCE));
+            } else {
+                debug(&format!("{}: skipped line: {}", SOURCE, line));
+                skipped += 1;
             }
         }
     }
-    
-    if formatted_entries.is_empty() {
-        Ok("No definitions found.".to_string())
-    } else {
-        Ok(formatted_entries.join("\n\n"))
+
+    // A page without entries is only a plain miss if it says so
+    let page_text = document.root_element().text().collect::<String>();
+    if entries.is_empty() && !page_text.contains("No matches") {
+        let summary: String = page_text.split_whitespace().collect::<Vec<_>>().join(" ").chars().take(300).collect();
+        debug(&format!("{}: unexpected page: {}", SOURCE, summary));
+        if skipped > 0 || document.select(&selector).next().is_none() {
+            return Err(format!("unexpected response from {} (--debug shows it)", SOURCE).into());
+        }
     }
+    
+    Ok(entries)
+}
+
+/// `word [reading] /gloss/gloss/` lines; EDICT entries always end in a slash
+fn is_edict_line(line: &str) -> bool {
+    let Some((headword, glosses)) = line.split_once(" /") else {
+        return false;
+    };
+    !headword.trim().is_empty() && !headword.contains('<') && glosses.ends_with('/')
 }
 
-fn format_entry(entry: &str) -> String {
+pub fn parse_entry(entry: &str) -> DictionaryEntry {
     // Parse entry format: "入る(P);這入る(rK) [はいる] /(v5r,vi) (1) (ant: 出る・1) to enter/to come in/to go in/to get in/to arrive/(v5r,vi) (2) to join (a club, company, etc.)/..."
     let parts: Vec<&str> = entry.split(" /").collect();
     if parts.len() < 2 {
-        return entry.to_string();
+        return unparsed_entry(entry);
     }
     
     let word_reading = parts[0];
@@ -48,7 +88,7 @@ fn format_entry(entry: &str) -> String {
     let pos_end = definition_part.find(')');
     
     if pos_start.is_none() || pos_end.is_none() {
-        return entry.to_string();
+        return unparsed_entry(entry);
     }
     
     let pos_start = pos_start.unwrap();
@@ -178,26 +218,60 @@ fn format_entry(entry: &str) -> String {
         }
     }
     
-    // Format output with colors
-    let mut result = String::new();
-    result.push_str(&word_reading.bright_cyan().to_string());
-    result.push('\n');
-    result.push_str(&pos.bright_yellow().to_string());
-    result.push('\n');
-    
-    // Format definitions
-    if definitions.len() == 1 {
-        result.push_str(&format!("  {}", definitions[0].bright_white()));
-    } else {
-        for (i, def) in definitions.iter().enumerate() {
-            if i > 0 {
-                result.push('\n');
-            }
-            result.push_str(&format!("  {} {}", 
-                format!("({})", i + 1).bright_magenta(),
-                def.bright_white()));
-        }
+    let references = definitions.iter()
+        .flat_map(|def| parse_edict_references(def))
+        .collect();
+
+    DictionaryEntry::new(word_reading.to_string(), pos.to_string(), definitions)
+        .with_references(references)
+}
+
+fn unparsed_entry(entry: &str) -> DictionaryEntry {
+    DictionaryEntry::new(entry.to_string(), String::new(), Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_only_edict_lines() {
+        let html = "<html><body><h2>WWWJDIC: Word Display</h2>\
+            <pre>\n\
+            Searching for: 入る\n\
+            Possible inflected verb or adjective: (passive) 入る\n\
+            入る(P);這入る(rK) [はいる] /(v5r,vi) (1) (ant: 出る・1) to enter/to come in/(P)/\n\
+            <b>Dictionary: EDICT</b>\n\
+            入れる(P) [いれる] /(v1,vt) to put in/(P)/\n\
+            </pre></body></html>";
+        let entries = parse_response(html).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].headword(), "入る");
+        assert_eq!(entries[0].reading(), "はいる");
+        assert_eq!(entries[1].headword(), "入れる");
+        assert!(entries.iter().all(|entry| entry.source == SOURCE));
     }
-    
-    result
-}
\ No newline at end of file
+
+    #[test]
+    fn accepts_an_empty_result() {
+        let html = "<html><body><p>No matches were found for this key.</p><pre></pre></body></html>";
+        assert!(parse_response(html).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_an_error_page() {
+        let html = "<html><body><h1>Service Unavailable</h1><p>Please try again later.</p></body></html>";
+        assert!(parse_response(html).is_err());
+
+        let html = "<html><body><pre>Internal error: database not loaded</pre></body></html>";
+        assert!(parse_response(html).is_err());
+    }
+
+    #[test]
+    fn recognizes_edict_lines() {
+        assert!(is_edict_line("入る [はいる] /(v5r,vi) to enter/"));
+        assert!(!is_edict_line("Searching for: 入る"));
+        assert!(!is_edict_line("<b>Dictionary</b> /x/"));
+        assert!(!is_edict_line("入る [はいる] /(v5r,vi) to enter"));
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{Dictionary, DictionaryEntry, DictionaryResult};
+use crate::format::{FormatOptions, format_entries};
+use crate::http;
+use crate::sense::{self, Sense};
+use scraper::{ElementRef, Html, Selector};
+
+pub const SOURCE: &str = "kotobank";
+
+/// Kotobank (kotobank.jp), which collects entries from several dictionaries on one page
+pub struct Kotobank;
+
+impl Dictionary for Kotobank {
+    fn name(&self) -> &'static str {
+        SOURCE
+    }
+
+    fn lookup(&self, term: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
+        lookup_kotobank(term)
+    }
+}
+
+pub fn search_kotobank(term: &str) -> DictionaryResult<String> {
+    let entries = lookup_kotobank(term)?;
+    Ok(format_entries(&entries, &FormatOptions::default()))
+}
+
+pub fn lookup_kotobank(term: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
+    let url = format!("https://kotobank.jp/word/{}", term);
+
+    let Some(html) = http::get_page(SOURCE, &url)? else {
+        return Ok(Vec::new());
+    };
+
+    let document = Html::parse_document(&html);
+
+    Ok(parse_kotobank_entries(&document))
+}
+
+/// One `article` per dictionary, titled by its `h2`, holding `div.ex` entries
+fn parse_kotobank_entries(document: &Html) -> Vec<DictionaryEntry> {
+    let article_selector = Selector::parse("article").unwrap();
+    let title_selector = Selector::parse("h2").unwrap();
+    let entry_selector = Selector::parse("div.ex").unwrap();
+
+    let mut entries = Vec::new();
+    for article in document.select(&article_selector) {
+        let dictionary = article.select(&title_selector).next()
+            .map(|title| dictionary_name(&title.text().collect::<String>()))
+            .unwrap_or_default();
+
+        for entry in article.select(&entry_selector) {
+            if let Some(entry) = parse_entry(&entry) {
+                entries.push(entry.with_dictionary(&dictionary));
+            }
+        }
+    }
+    entries
+}
+
+/// "デジタル大辞泉「正解」の解説" → "デジタル大辞泉"
+fn dictionary_name(title: &str) -> String {
+    let title = title.trim();
+    match title.find('「') {
+        Some(quote) if title.ends_with("の解説") => title[..quote].trim().to_string(),
+        _ => title.to_string(),
+    }
+}
+
+fn parse_entry(entry: &ElementRef) -> Option<DictionaryEntry> {
+    let headword_selector = Selector::parse("h3").unwrap();
+    let description_selector = Selector::parse("section.description").unwrap();
+
+    let word_reading = entry.select(&headword_selector).next()?
+        .text().collect::<String>()
+        .trim()
+        .replace('‐', "");
+    let description = entry.select(&description_selector).next()?
+        .text().collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    // The part of speech leads the description, e.g. ［名］(スル)
+    let (part_of_speech, senses) = sense::split_description(&description);
+
+    let definitions: Vec<String> = senses.iter().map(Sense::plain).filter(|text| !text.is_empty()).collect();
+    if definitions.is_empty() {
+        return None;
+    }
+
+    let entry = DictionaryEntry::new(word_reading, part_of_speech, definitions)
+        .with_senses(senses)
+        .with_source(SOURCE);
+    Some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_text_before_numbered_senses() {
+        let document = Html::parse_document(
+            "<article><h2>デジタル大辞泉「思う」の解説</h2>\
+            <div class=\"ex\"><h3>おも・う【思う】</h3>\
+            <section class=\"description\">［動ワ五(ハ四)］ 心に浮かべる働きをいう。\
+            １ 判断する。「正しいと―・う」 ２ 予想する。</section></div></article>",
+        );
+        let entries = parse_kotobank_entries(&document);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].dictionary, "デジタル大辞泉");
+        assert_eq!(entries[0].part_of_speech, "［動ワ五(ハ四)］");
+        let labels: Vec<&str> = entries[0].senses.iter().map(|sense| sense.label.as_str()).collect();
+        assert_eq!(labels, ["", "１", "２"]);
+        assert_eq!(entries[0].senses[0].text, "心に浮かべる働きをいう。");
+        assert_eq!(entries[0].senses[1].examples, ["「正しいと―・う」"]);
+    }
+}
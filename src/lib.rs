@@ -1,16 +1,123 @@
+pub mod anki;
+pub mod batch;
+pub mod clipboard;
+pub mod config;
+pub mod deinflect;
+pub mod ejje_search;
+pub mod fallback;
+pub mod format;
+pub mod furigana;
+pub mod goo_search;
+pub mod history;
+pub mod http;
+pub mod jisho_api;
 pub mod jisho_search;
+pub mod kotobank_search;
+pub mod offline;
+pub mod pager;
+pub mod pitch_accent;
+pub mod reference;
+pub mod render;
+pub mod repl;
+pub mod segment;
+pub mod sense;
+pub mod session;
+pub mod theme;
+pub mod thesaurus;
+pub mod tui;
 pub mod weblio_search;
+pub mod wrap;
 
+use reference::Reference;
+use sense::{ExampleSentence, Sense};
+use serde::{Deserialize, Serialize};
+use std::env;
 use std::error::Error;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 pub type DictionaryResult<T> = Result<T, Box<dyn Error>>;
 
-#[derive(Debug, Clone)]
+/// A dictionary backend that can look up a term. Backends are shared between the
+/// worker threads of a batch lookup.
+pub trait Dictionary: Send + Sync {
+    /// Short backend name, also recorded as the `source` of its entries
+    fn name(&self) -> &'static str;
+
+    /// Backends asked by a lookup, in order. Results are cached under the one that answered.
+    fn backends(&self) -> Vec<&'static str> {
+        vec![self.name()]
+    }
+
+    fn lookup(&self, term: &str) -> DictionaryResult<Vec<DictionaryEntry>>;
+}
+
+/// Backend names accepted by `dictionary_by_name`
+pub const BACKENDS: &[&str] = &[
+    jisho_search::SOURCE,
+    jisho_api::SOURCE,
+    weblio_search::SOURCE,
+    ejje_search::SOURCE,
+    goo_search::SOURCE,
+    kotobank_search::SOURCE,
+    offline::SOURCE,
+    thesaurus::SOURCE,
+];
+
+/// Other names accepted for backends: `jisho` is wwwjdic everywhere (`-b`, the config file,
+/// `:jisho` in the REPL), as it was before the jisho.org API backend, which is `jisho.org`
+pub const BACKEND_ALIASES: &[(&str, &str)] = &[("jisho", jisho_search::SOURCE)];
+
+/// Whether `name` is a backend or an alias of one
+pub fn is_backend(name: &str) -> bool {
+    BACKENDS.contains(&name) || BACKEND_ALIASES.iter().any(|(alias, _)| *alias == name)
+}
+
+/// Backend by name or alias
+pub fn dictionary_by_name(name: &str) -> DictionaryResult<Box<dyn Dictionary>> {
+    let name = BACKEND_ALIASES.iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |(_, backend)| *backend);
+    match name {
+        "wwwjdic" => Ok(Box::new(jisho_search::Wwwjdic)),
+        "jisho.org" => Ok(Box::new(jisho_api::JishoOrg)),
+        "weblio" => Ok(Box::new(weblio_search::Weblio)),
+        "ejje" => Ok(Box::new(ejje_search::Ejje)),
+        "goo" => Ok(Box::new(goo_search::Goo)),
+        "kotobank" => Ok(Box::new(kotobank_search::Kotobank)),
+        "offline" => Ok(Box::new(offline::OfflineDictionary::load_default()?)),
+        "thesaurus" => Ok(Box::new(thesaurus::Thesaurus)),
+        other => Err(format!("unknown backend: {} (expected {})", other, BACKENDS.join(", ")).into()),
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DictionaryEntry {
     pub word_reading: String,
     pub part_of_speech: String,
     pub definitions: Vec<String>,
+    /// Definitions as a tree with sub-senses and examples, when the dictionary has them.
+    /// `definitions` holds the same senses flattened to one line each.
+    pub senses: Vec<Sense>,
+    /// Example sentences with translations, from bilingual dictionaries
+    pub example_sentences: Vec<ExampleSentence>,
     pub synonyms: Vec<String>,
+    /// Cross-references to other headwords (synonyms, antonyms, "see also")
+    pub references: Vec<Reference>,
+    pub pitch_accent: Vec<u8>,
+    /// Backend the entry was looked up in, e.g. "wwwjdic" or "weblio"
+    pub source: String,
+    /// Other ways of writing the headword, e.g. 思う and 想う
+    pub notations: Vec<String>,
+    /// Reading in historical kana (歴史的仮名遣い), e.g. おもふ for おもう
+    pub historical_kana: String,
+    /// Origin of the word as the dictionary gives it, e.g. 「思ひ」の転
+    pub etymology: String,
+    /// Labels such as "common" or "JLPT N5"
+    pub tags: Vec<String>,
+    /// Dictionary within the backend, e.g. "デジタル大辞泉" for Weblio pages that bundle several
+    pub dictionary: String,
 }
 
 impl DictionaryEntry {
@@ -19,7 +126,17 @@ impl DictionaryEntry {
             word_reading,
             part_of_speech,
             definitions,
+            senses: Vec::new(),
+            example_sentences: Vec::new(),
             synonyms: Vec::new(),
+            references: Vec::new(),
+            pitch_accent: Vec::new(),
+            source: String::new(),
+            notations: Vec::new(),
+            historical_kana: String::new(),
+            etymology: String::new(),
+            tags: Vec::new(),
+            dictionary: String::new(),
         }
     }
 
@@ -27,4 +144,130 @@ impl DictionaryEntry {
         self.synonyms = synonyms;
         self
     }
-}
\ No newline at end of file
+
+    pub fn with_references(mut self, references: Vec<Reference>) -> Self {
+        self.references = references;
+        self
+    }
+
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source = source.to_string();
+        self
+    }
+
+    pub fn with_senses(mut self, senses: Vec<Sense>) -> Self {
+        self.senses = senses;
+        self
+    }
+
+    pub fn with_example_sentences(mut self, example_sentences: Vec<ExampleSentence>) -> Self {
+        self.example_sentences = example_sentences;
+        self
+    }
+
+    pub fn with_notations(mut self, notations: Vec<String>) -> Self {
+        self.notations = notations;
+        self
+    }
+
+    pub fn with_historical_kana(mut self, historical_kana: &str) -> Self {
+        self.historical_kana = historical_kana.to_string();
+        self
+    }
+
+    pub fn with_etymology(mut self, etymology: &str) -> Self {
+        self.etymology = etymology.to_string();
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// Labeled notation variants, historical kana, etymology and tags, for a line under the headword
+    pub fn metadata(&self) -> Vec<(&'static str, String)> {
+        let fields = [
+            ("表記", self.notations.join("・")),
+            ("歴史的仮名遣い", self.historical_kana.clone()),
+            ("語源", self.etymology.clone()),
+            ("タグ", self.tags.join(", ")),
+        ];
+        fields.into_iter().filter(|(_, value)| !value.is_empty()).collect()
+    }
+
+    pub fn with_dictionary(mut self, dictionary: &str) -> Self {
+        self.dictionary = dictionary.to_string();
+        self
+    }
+
+    /// Whether the entry comes from one of `dictionaries`, matched as substrings so that
+    /// "大辞泉" selects "デジタル大辞泉". Entries without a dictionary always match.
+    pub fn in_dictionaries(&self, dictionaries: &[String]) -> bool {
+        self.dictionary.is_empty() || dictionaries.iter().any(|name| self.dictionary.contains(name.as_str()))
+    }
+
+    pub fn with_pitch_accent(mut self, pitch_accent: Vec<u8>) -> Self {
+        self.pitch_accent = pitch_accent;
+        self
+    }
+
+    /// Primary written form, e.g. `入る` for "入る(P);這入る(rK) [はいる]" or `正解` for "せいかい【正解】"
+    pub fn headword(&self) -> String {
+        primary_form(self.split_word_reading().0)
+    }
+
+    /// Primary kana reading, e.g. `はいる` for "入る(P);這入る(rK) [はいる]"
+    pub fn reading(&self) -> String {
+        primary_form(self.split_word_reading().1)
+    }
+
+    fn split_word_reading(&self) -> (&str, &str) {
+        let text = self.word_reading.trim();
+
+        // Weblio style: reading【word】
+        if let (Some(open), Some(close)) = (text.find('【'), text.find('】'))
+            && open < close
+        {
+            return (&text[open + '【'.len_utf8()..close], &text[..open]);
+        }
+
+        // EDICT style: word [reading]
+        if let (Some(open), Some(close)) = (text.find('['), text.rfind(']'))
+            && open < close
+        {
+            return (text[..open].trim(), &text[open + 1..close]);
+        }
+
+        // Kana-only entries carry no separate reading
+        (text, text)
+    }
+}
+
+fn primary_form(forms: &str) -> String {
+    let first = forms.split([';', '・']).next().unwrap_or("");
+    let without_tags = first.split('(').next().unwrap_or("");
+    without_tags.trim().replace('‐', "")
+}
+
+static DEBUG: AtomicBool = AtomicBool::new(false);
+
+/// Report what the backends skip while parsing (`--debug` or `$JISHO_DEBUG`)
+pub fn set_debug(enabled: bool) {
+    DEBUG.store(enabled, Ordering::Relaxed);
+}
+
+/// Print `message` to stderr in debug mode
+pub fn debug(message: &str) {
+    if DEBUG.load(Ordering::Relaxed) {
+        eprintln!("debug: {}", message);
+    }
+}
+
+/// Directory for locally stored jisho data files (`$XDG_DATA_HOME/jisho` or `~/.local/share/jisho`)
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir).join("jisho"));
+    }
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share/jisho"))
+}
@@ -1,48 +1,780 @@
 use std::env;
-use jisho::jisho_search::search_jisho;
-use jisho::weblio_search::search_weblio;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::PathBuf;
+use std::time::Duration;
+use jisho::{Dictionary, DictionaryEntry, DictionaryResult, dictionary_by_name};
+use jisho::anki::{self, NoteType};
+use jisho::batch;
+use jisho::clipboard::{self, ClipboardProvider};
+use jisho::config::{self, Config};
+use jisho::fallback::{self, Fallback};
+use jisho::format::FormatOptions;
+use jisho::furigana::FuriganaStyle;
+use jisho::history::{self, History, HistoryRecord};
+use jisho::http::{self, HttpSettings};
+use jisho::offline::{self, OfflineDictionary};
+use jisho::pager;
+use jisho::pitch_accent::{self, AccentDictionary};
+use jisho::reference::all_references;
+use jisho::render::OutputFormat;
+use jisho::repl;
+use jisho::segment;
+use jisho::session::Session;
+use jisho::theme::{self, Theme};
+use jisho::thesaurus;
+use jisho::tui;
+use jisho::wrap;
+
+struct Options {
+    backend: String,
+    fallback: Vec<String>,
+    interactive: bool,
+    tui: bool,
+    watch: bool,
+    segment: bool,
+    particles: bool,
+    edict: Option<PathBuf>,
+    accent_dict: Option<PathBuf>,
+    format: FormatOptions,
+    output_format: OutputFormat,
+    theme: String,
+    limit: Option<usize>,
+    dictionaries: Vec<String>,
+    pager: bool,
+    follow: Option<usize>,
+    search_term: String,
+}
+
+struct ExportOptions {
+    backend: String,
+    accent_dict: Option<PathBuf>,
+    output: PathBuf,
+    selection: Option<Vec<usize>>,
+    note_type: NoteType,
+    deck: String,
+    search_terms: Vec<String>,
+}
+
+struct BatchOptions {
+    backend: String,
+    accent_dict: Option<PathBuf>,
+    format: FormatOptions,
+    output_format: OutputFormat,
+    theme: String,
+    dictionaries: Vec<String>,
+    output: Option<PathBuf>,
+    pager: bool,
+    jobs: usize,
+    /// Requests per second to the backend, unlimited if None
+    rate: Option<f64>,
+    input: Option<PathBuf>,
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    
+    let mut args: Vec<String> = env::args().collect();
+    // --debug works with every mode, so it is taken out before the arguments are parsed
+    let debug = args.iter().any(|arg| arg == "--debug");
+    args.retain(|arg| arg != "--debug");
+    jisho::set_debug(debug || env::var_os("JISHO_DEBUG").is_some_and(|value| !value.is_empty()));
+    theme::configure_color();
+    let config = load_config();
+
+    if args.get(1).map(String::as_str) == Some("config") {
+        match (&config.path, config::default_path()) {
+            (Some(path), _) => println!("# {}", path.display()),
+            (None, Some(path)) => println!("# {} (not found, showing defaults)", path.display()),
+            (None, None) => println!("# no config file (HOME is not set)"),
+        }
+        println!("{}", config.describe());
+        return;
+    }
+
+    if args.get(1).map(String::as_str) == Some("export") {
+        let options = parse_export_args(&args, &config);
+        if let Err(e) = run_export(options, &config) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if args.get(1).map(String::as_str) == Some("thesaurus") {
+        set_theme(&config.theme);
+        if let Err(e) = run_thesaurus(&args, &config) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if args.get(1).map(String::as_str) == Some("batch") {
+        let options = parse_batch_args(&args, &config);
+        set_theme(&options.theme);
+        if let Err(e) = run_batch(options, &config) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if args.get(1).map(String::as_str) == Some("history") {
+        set_theme(&config.theme);
+        if let Err(e) = run_history(&args, &config) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     // Parse arguments
-    let (use_weblio, search_term) = parse_args(&args);
-    
-    let result = if use_weblio {
-        search_weblio(&search_term)
-    } else {
-        search_jisho(&search_term)
-    };
-    
+    let options = parse_args(&args, &config);
+    set_theme(&options.theme);
+
+    if options.segment {
+        match segment_sentence(&options) {
+            Ok(output) => print_output(&output, options.output_format, options.pager),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let mut session = build_session(options.accent_dict.clone(), options.limit, &options.dictionaries, &config);
+    let dictionary = open_dictionary(&options.backend, &options.fallback);
+
+    if options.interactive {
+        if let Err(e) = repl::run(session, dictionary, options.format) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if options.tui {
+        let initial_term = Some(options.search_term.as_str()).filter(|term| !term.is_empty());
+        if let Err(e) = tui::run(session, dictionary, initial_term) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if options.watch {
+        let Some(provider) = ClipboardProvider::detect() else {
+            eprintln!("Error: no clipboard provider found (install wl-clipboard, xclip or xsel, or set JISHO_CLIPBOARD_CMD)");
+            std::process::exit(1);
+        };
+        if let Err(e) = clipboard::watch(&provider, &mut session, dictionary.as_ref(), &options.format, Duration::from_millis(500)) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let result = session.lookup(dictionary.as_ref(), &options.search_term)
+        .and_then(|entries| match options.follow {
+            Some(number) => follow(&mut session, dictionary.as_ref(), &entries, number, options.format, options.output_format),
+            None => {
+                let mut output = options.output_format.renderer().render(&entries, &options.format);
+                // Say which backend of the chain answered; other formats carry it in the entries
+                let sources = fallback::sources(&entries);
+                if !options.fallback.is_empty() && !sources.is_empty() && options.output_format == OutputFormat::Text {
+                    let note = theme::current().muted.paint(&format!("via {}", sources.join(", ")));
+                    output = format!("{}\n{}", note, output);
+                }
+                Ok(output)
+            }
+        });
+
     match result {
-        Ok(definition) => println!("\n{}\n", definition),
+        Ok(output) => print_output(&output, options.output_format, options.pager),
         Err(e) => eprintln!("Error: {}", e),
     }
 }
 
-fn parse_args(args: &[String]) -> (bool, String) {
-    if args.len() < 2 {
-        eprintln!("Usage: {} [-j] <japanese_word>", args[0]);
-        eprintln!("  -j: Use Japanese monolingual dictionary (Weblio)");
+fn print_output(output: &str, format: OutputFormat, use_pager: bool) {
+    // Terminal output gets some space around it, documents are printed as they are
+    if format == OutputFormat::Text {
+        pager::print(&format!("\n{}\n", output), use_pager);
+    } else {
+        pager::print(output.trim_end(), use_pager);
+    }
+}
+
+fn follow(
+    session: &mut Session,
+    dictionary: &dyn Dictionary,
+    entries: &[DictionaryEntry],
+    number: usize,
+    format: FormatOptions,
+    output_format: OutputFormat,
+) -> DictionaryResult<String> {
+    let references = all_references(entries);
+    let reference = references.get(number - 1)
+        .ok_or_else(|| format!("no reference {} ({} found)", number, references.len()))?;
+
+    let followed = session.follow(dictionary, reference)?;
+    let format = FormatOptions { highlight_sense: reference.sense, ..format };
+    let rendered = output_format.renderer().render(&followed, &format);
+    if output_format == OutputFormat::Text {
+        Ok(format!("→ {}\n\n{}", reference.term, rendered))
+    } else {
+        Ok(rendered)
+    }
+}
+
+fn segment_sentence(options: &Options) -> DictionaryResult<String> {
+    let path = options.edict.clone().or_else(offline::default_path)
+        .ok_or("segmentation needs an offline dictionary (--edict <file> or $JISHO_EDICT)")?;
+    let dictionary = OfflineDictionary::load(&path)?;
+    let tokens = segment::segment(&options.search_term, &dictionary);
+    let renderer = options.output_format.renderer();
+    Ok(segment::format_glossary(&tokens, options.particles, renderer.as_ref(), &options.format))
+}
+
+/// The backend, or a chain of it and the `fallback` backends after it. Backends of a
+/// chain that can't be opened (e.g. offline without a dictionary file) are left out.
+fn open_dictionary(name: &str, fallback: &[String]) -> Box<dyn Dictionary> {
+    if fallback.is_empty() {
+        return match dictionary_by_name(name) {
+            Ok(dictionary) => dictionary,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        };
+    }
+
+    let mut names = vec![name];
+    names.extend(fallback.iter().map(String::as_str).filter(|backend| *backend != name));
+    let mut dictionaries = Vec::new();
+    for backend in names {
+        match dictionary_by_name(backend) {
+            Ok(dictionary) => dictionaries.push(dictionary),
+            Err(e) => eprintln!("Warning: skipping {}: {}", backend, e),
+        }
+    }
+    if dictionaries.is_empty() {
+        eprintln!("Error: none of the fallback backends could be opened");
         std::process::exit(1);
     }
-    
-    if args.len() == 2 {
-        // No flags, just the search term
-        (false, args[1].clone())
-    } else if args.len() == 3 {
-        // Check for -j flag
-        if args[1] == "-j" {
-            (true, args[2].clone())
-        } else {
-            eprintln!("Unknown flag: {}", args[1]);
-            eprintln!("Usage: {} [-j] <japanese_word>", args[0]);
-            std::process::exit(1);
+    Box::new(Fallback::new(dictionaries))
+}
+
+/// The config file and environment settings, or the defaults if the file is broken
+fn load_config() -> Config {
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("Warning: {}", e);
+        Config::default()
+    });
+
+    http::configure(HttpSettings {
+        timeout: config.http_timeout,
+        backend_timeouts: config.backend_timeouts.clone(),
+        user_agent: config.user_agent.clone(),
+        retries: config.http_retries,
+    });
+    http::limit_rate(config.rate_limit);
+    if let Some(path) = &config.edict {
+        offline::set_default_path(path.clone());
+    }
+    config
+}
+
+fn build_session(accent_dict: Option<PathBuf>, limit: Option<usize>, dictionaries: &[String], config: &Config) -> Session {
+    let mut session = Session::new().with_dictionaries(dictionaries.to_vec());
+    if let Some(limit) = limit {
+        session = session.with_limit(limit);
+    }
+    if let Some(ttl) = config.cache_ttl {
+        session = session.with_cache_ttl(ttl);
+    }
+    if let Some(accents) = load_accents(accent_dict) {
+        session = session.with_accents(accents);
+    }
+    if let Some(path) = History::default_path() {
+        session = session.with_history(path);
+    }
+    session
+}
+
+fn run_history(args: &[String], config: &Config) -> DictionaryResult<()> {
+    let path = History::default_path().ok_or("could not determine the history location (HOME is not set)")?;
+    let mut history = History::open(&path)?;
+
+    let command = args.get(2).map(String::as_str).unwrap_or("list");
+    let rest = args.get(3..).unwrap_or_default();
+    let starred_only = rest.iter().any(|arg| arg == "--starred");
+
+    match command {
+        "list" => {
+            let records: Vec<&HistoryRecord> = history.records().iter()
+                .filter(|record| record.starred || !starred_only)
+                .collect();
+            print_history(&records);
+        }
+        "search" => {
+            let query = rest.first().ok_or("history search requires a query")?;
+            print_history(&history.search(query));
         }
+        "show" => {
+            let record = history.get(parse_history_id(rest)?).ok_or("no such history entry")?;
+            let mut format = FormatOptions { furigana: config.furigana, width: wrap::terminal_width(), ..FormatOptions::default() };
+            if let Some(position) = rest.iter().position(|arg| arg == "--furigana") {
+                let style = rest.get(position + 1).ok_or("--furigana requires a style (inline or ruby)")?;
+                format.furigana = Some(style.parse::<FuriganaStyle>()?);
+            }
+            let output_format = match rest.iter().position(|arg| arg == "--format") {
+                Some(position) => rest.get(position + 1).ok_or("--format requires a format")?.parse::<OutputFormat>()?,
+                None => config.format,
+            };
+            let use_pager = !rest.iter().any(|arg| arg == "--no-pager");
+            print_output(&output_format.renderer().render(&record.entries, &format), output_format, use_pager);
+        }
+        "star" | "unstar" => {
+            let id = parse_history_id(rest)?;
+            history.set_starred(id, command == "star")?;
+        }
+        "export" => {
+            let options = parse_export_flags(&args[0], rest, config);
+            let records: Vec<&HistoryRecord> = history.records().iter()
+                .filter(|record| record.starred || !starred_only)
+                .collect();
+
+            let is_json = options.output.extension().is_some_and(|ext| ext == "jsonl" || ext == "json");
+            if is_json {
+                let mut contents = String::new();
+                for record in &records {
+                    contents.push_str(&serde_json::to_string(record)?);
+                    contents.push('\n');
+                }
+                std::fs::write(&options.output, contents)?;
+                println!("Exported {} lookups to {}", records.len(), options.output.display());
+            } else {
+                let entries: Vec<DictionaryEntry> = records.iter()
+                    .flat_map(|record| record.entries.iter().cloned())
+                    .collect();
+                write_anki(&options, &entries)?;
+            }
+        }
+        other => return Err(format!("unknown history command: {} (expected list, search, show, star, unstar or export)", other).into()),
+    }
+
+    Ok(())
+}
+
+fn parse_history_id(args: &[String]) -> DictionaryResult<u64> {
+    let id = args.first().ok_or("missing history entry number")?;
+    Ok(id.parse().map_err(|_| format!("invalid history entry number: {}", id))?)
+}
+
+fn print_history(records: &[&HistoryRecord]) {
+    for record in records {
+        println!("{:>5} {} {} {:<8} {} ({} entries)",
+            record.id,
+            if record.starred { "★" } else { " " },
+            history::format_timestamp(record.timestamp),
+            record.backend,
+            record.term,
+            record.entries.len());
+    }
+}
+
+fn run_thesaurus(args: &[String], config: &Config) -> DictionaryResult<()> {
+    let mut output_format = config.format;
+    let mut use_pager = true;
+    let mut term = None;
+
+    let mut iter = args.iter().skip(2);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => output_format = iter.next().ok_or("--format requires a format")?.parse()?,
+            "--no-pager" => use_pager = false,
+            flag if flag.starts_with('-') => return Err(format!("unknown flag: {}", flag).into()),
+            word if term.is_none() => term = Some(word.to_string()),
+            _ => return Err("thesaurus takes a single word".into()),
+        }
+    }
+    let term = term.ok_or("missing search term")?;
+
+    let groups = thesaurus::lookup_thesaurus(&term)?;
+    // Other formats get one entry per group, like `-b thesaurus`
+    let output = match output_format {
+        OutputFormat::Text => thesaurus::format_groups(&term, &groups, wrap::terminal_width()),
+        format => format.renderer().render(&thesaurus::group_entries(&term, &groups), &FormatOptions::default()),
+    };
+    print_output(&output, output_format, use_pager);
+    Ok(())
+}
+
+fn run_batch(options: BatchOptions, config: &Config) -> DictionaryResult<()> {
+    let terms = match &options.input {
+        Some(path) if path.as_os_str() != "-" => batch::read_terms(BufReader::new(File::open(path)?))?,
+        _ => batch::read_terms(io::stdin().lock())?,
+    };
+
+    let mut session = build_session(options.accent_dict.clone(), config.limit, &options.dictionaries, config);
+    let dictionary = open_dictionary(&options.backend, &config.fallback);
+    http::limit_rate(options.rate);
+    let results = batch::lookup_all(&mut session, dictionary.as_ref(), &terms, options.jobs);
+
+    match &options.output {
+        Some(path) => {
+            colored::control::set_override(false);
+            let format = FormatOptions { width: None, ..options.format };
+            let mut output = batch::format_results(&results, options.output_format, &format);
+            output.push('\n');
+            fs::write(path, output)?;
+            println!("Wrote {} terms to {}", results.len(), path.display());
+        }
+        None => pager::print(&batch::format_results(&results, options.output_format, &options.format), options.pager),
+    }
+
+    if let Some(report) = batch::report_misses(&results) {
+        eprintln!("{}", report);
+    }
+    Ok(())
+}
+
+fn run_export(options: ExportOptions, config: &Config) -> DictionaryResult<()> {
+    // Export selections are numbered over all entries, so the result limit doesn't apply
+    let mut session = build_session(options.accent_dict.clone(), None, &config.dictionaries, config);
+    let dictionary = open_dictionary(&options.backend, &config.fallback);
+
+    let mut entries = Vec::new();
+    for term in &options.search_terms {
+        entries.extend(session.lookup(dictionary.as_ref(), term)?);
+    }
+
+    // Selection numbers refer to entries in the order they are displayed
+    let selected: Vec<DictionaryEntry> = match &options.selection {
+        Some(numbers) => {
+            let mut selected = Vec::new();
+            for &number in numbers {
+                match number.checked_sub(1).and_then(|i| entries.get(i)) {
+                    Some(entry) => selected.push(entry.clone()),
+                    None => return Err(format!("no entry {} ({} found)", number, entries.len()).into()),
+                }
+            }
+            selected
+        }
+        None => entries,
+    };
+
+    if selected.is_empty() {
+        return Err("No definitions found.".into());
+    }
+
+    write_anki(&options, &selected)
+}
+
+fn write_anki(options: &ExportOptions, entries: &[DictionaryEntry]) -> DictionaryResult<()> {
+    let is_apkg = options.output.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("apkg"));
+    if is_apkg {
+        anki::write_apkg(&options.output, entries, &options.note_type, &options.deck)?;
     } else {
-        eprintln!("Too many arguments");
-        eprintln!("Usage: {} [-j] <japanese_word>", args[0]);
-        std::process::exit(1);
+        anki::write_tsv(&options.output, entries, &options.note_type)?;
+    }
+
+    for entry in entries {
+        println!("{}", entry.word_reading);
+    }
+    println!("Exported {} entries to {}", entries.len(), options.output.display());
+    Ok(())
+}
+
+fn set_theme(name: &str) {
+    match Theme::load_named(name) {
+        Ok(loaded) => theme::set(loaded),
+        Err(e) => eprintln!("Warning: {}", e),
+    }
+}
+
+fn load_accents(accent_dict: Option<PathBuf>) -> Option<AccentDictionary> {
+    // An explicitly requested dictionary must exist, the default location is optional
+    let (path, explicit) = match accent_dict {
+        Some(path) => (path, true),
+        None => (pitch_accent::default_path()?, false),
+    };
+
+    if !explicit && !path.exists() {
+        return None;
+    }
+
+    match AccentDictionary::load(&path) {
+        Ok(dictionary) => Some(dictionary),
+        Err(e) => {
+            eprintln!("Warning: {}", e);
+            None
+        }
     }
 }
 
+fn parse_args(args: &[String], config: &Config) -> Options {
+    let mut options = Options {
+        backend: config.backend.clone(),
+        fallback: config.fallback.clone(),
+        interactive: false,
+        tui: false,
+        watch: false,
+        segment: false,
+        particles: false,
+        edict: config.edict.clone(),
+        accent_dict: config.accent_dict.clone(),
+        format: FormatOptions { furigana: config.furigana, width: wrap::terminal_width(), ..FormatOptions::default() },
+        output_format: config.format,
+        theme: config.theme.clone(),
+        limit: config.limit,
+        dictionaries: config.dictionaries.clone(),
+        pager: true,
+        follow: None,
+        search_term: String::new(),
+    };
+    let mut search_terms = Vec::new();
+
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-j" => options.backend = "weblio".to_string(),
+            "-b" | "--backend" => match iter.next() {
+                Some(name) => options.backend = name.clone(),
+                None => usage_error(&args[0], "--backend requires a backend name"),
+            },
+            "--fallback" => match iter.next() {
+                Some(names) => options.fallback = config::parse_list(names),
+                None => usage_error(&args[0], "--fallback requires backend names"),
+            },
+            "--no-fallback" => options.fallback.clear(),
+            "--limit" => match iter.next().map(|limit| limit.parse::<usize>()) {
+                Some(Ok(limit)) => options.limit = Some(limit).filter(|&limit| limit > 0),
+                _ => usage_error(&args[0], "--limit requires a number (0 for no limit)"),
+            },
+            "--dict" => match iter.next() {
+                Some(names) => options.dictionaries = config::parse_list(names),
+                None => usage_error(&args[0], "--dict requires dictionary names"),
+            },
+            "--no-pager" => options.pager = false,
+            "-i" => options.interactive = true,
+            "-t" | "--tui" => options.tui = true,
+            "-w" | "--watch" => options.watch = true,
+            "-s" | "--segment" => options.segment = true,
+            "--particles" => options.particles = true,
+            "--edict" => match iter.next() {
+                Some(path) => options.edict = Some(PathBuf::from(path)),
+                None => usage_error(&args[0], "--edict requires a file path"),
+            },
+            "--accent" => match iter.next() {
+                Some(path) => options.accent_dict = Some(PathBuf::from(path)),
+                None => usage_error(&args[0], "--accent requires a file path"),
+            },
+            "--theme" => match iter.next() {
+                Some(name) => options.theme = name.clone(),
+                None => usage_error(&args[0], "--theme requires a theme (dark, light or a theme file)"),
+            },
+            "--follow" => match iter.next().map(|number| number.parse::<usize>()) {
+                Some(Ok(number)) if number > 0 => options.follow = Some(number),
+                _ => usage_error(&args[0], "--follow requires a reference number"),
+            },
+            "--format" => match iter.next().map(|format| format.parse::<OutputFormat>()) {
+                Some(Ok(format)) => options.output_format = format,
+                Some(Err(e)) => usage_error(&args[0], &e),
+                None => usage_error(&args[0], "--format requires a format (text, markdown, html, json or tsv)"),
+            },
+            "--furigana" => match iter.next().map(|style| style.parse::<FuriganaStyle>()) {
+                Some(Ok(style)) => options.format.furigana = Some(style),
+                Some(Err(e)) => usage_error(&args[0], &e),
+                None => usage_error(&args[0], "--furigana requires a style (inline or ruby)"),
+            },
+            flag if flag.starts_with('-') => usage_error(&args[0], &format!("Unknown flag: {}", flag)),
+            term => search_terms.push(term.to_string()),
+        }
+    }
+
+    match search_terms.len() {
+        0 if options.interactive || options.tui || options.watch => {}
+        0 => usage_error(&args[0], "Missing search term"),
+        1 => options.search_term = search_terms.remove(0),
+        // Unquoted sentences arrive split on spaces
+        _ if options.segment => options.search_term = search_terms.join(" "),
+        _ => usage_error(&args[0], "Too many arguments"),
+    }
+
+    options
+}
+
+fn parse_batch_args(args: &[String], config: &Config) -> BatchOptions {
+    let program = &args[0];
+    let mut options = BatchOptions {
+        backend: config.backend.clone(),
+        accent_dict: config.accent_dict.clone(),
+        format: FormatOptions { furigana: config.furigana, width: wrap::terminal_width(), ..FormatOptions::default() },
+        output_format: config.format,
+        theme: config.theme.clone(),
+        dictionaries: config.dictionaries.clone(),
+        output: None,
+        pager: true,
+        jobs: batch::DEFAULT_JOBS,
+        rate: config.rate_limit.or(Some(batch::DEFAULT_RATE)),
+        input: None,
+    };
+
+    let mut iter = args.iter().skip(2);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-j" => options.backend = "weblio".to_string(),
+            "-o" | "--output" | "--accent" | "--format" | "--furigana" | "--jobs" | "--rate" | "--theme" | "-b" | "--backend" | "--dict" => {
+                let Some(value) = iter.next() else {
+                    usage_error(program, &format!("{} requires a value", arg));
+                };
+                match arg.as_str() {
+                    "--accent" => options.accent_dict = Some(PathBuf::from(value)),
+                    "--theme" => options.theme = value.clone(),
+                    "-b" | "--backend" => options.backend = value.clone(),
+                    "--dict" => options.dictionaries = config::parse_list(value),
+                    "--format" => match value.parse() {
+                        Ok(format) => options.output_format = format,
+                        Err(e) => usage_error(program, &e),
+                    },
+                    "--furigana" => match value.parse() {
+                        Ok(style) => options.format.furigana = Some(style),
+                        Err(e) => usage_error(program, &e),
+                    },
+                    "--jobs" => match value.parse::<usize>() {
+                        Ok(jobs) if jobs > 0 => options.jobs = jobs,
+                        _ => usage_error(program, "--jobs requires a positive number"),
+                    },
+                    "--rate" => match value.parse::<f64>() {
+                        Ok(rate) if rate >= 0.0 => options.rate = Some(rate).filter(|&rate| rate > 0.0),
+                        _ => usage_error(program, "--rate requires requests per second (0 for no limit)"),
+                    },
+                    _ => options.output = Some(PathBuf::from(value)),
+                }
+            }
+            "--no-pager" => options.pager = false,
+            "-" => options.input = Some(PathBuf::from("-")),
+            flag if flag.starts_with('-') => usage_error(program, &format!("Unknown flag: {}", flag)),
+            path if options.input.is_none() => options.input = Some(PathBuf::from(path)),
+            _ => usage_error(program, "batch takes a single input file"),
+        }
+    }
+
+    options
+}
+
+fn parse_export_args(args: &[String], config: &Config) -> ExportOptions {
+    let options = parse_export_flags(&args[0], &args[2..], config);
+    if options.search_terms.is_empty() {
+        usage_error(&args[0], "Missing search term");
+    }
+    options
+}
+
+fn parse_export_flags(program: &str, args: &[String], config: &Config) -> ExportOptions {
+    let mut options = ExportOptions {
+        backend: config.backend.clone(),
+        accent_dict: config.accent_dict.clone(),
+        output: PathBuf::new(),
+        selection: None,
+        note_type: NoteType::default(),
+        deck: "Jisho".to_string(),
+        search_terms: Vec::new(),
+    };
+    let mut field_mapping = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-j" => options.backend = "weblio".to_string(),
+            "--starred" => {}
+            "-o" | "--output" | "--accent" | "--select" | "--note-type" | "--fields" | "--deck" | "-b" | "--backend" => {
+                let Some(value) = iter.next() else {
+                    usage_error(program, &format!("{} requires a value", arg));
+                };
+                match arg.as_str() {
+                    "--accent" => options.accent_dict = Some(PathBuf::from(value)),
+                    "--select" => match parse_selection(value) {
+                        Some(selection) => options.selection = Some(selection),
+                        None => usage_error(program, &format!("Invalid selection: {}", value)),
+                    },
+                    "--note-type" => options.note_type.name = value.clone(),
+                    "--fields" => field_mapping = Some(value.clone()),
+                    "--deck" => options.deck = value.clone(),
+                    "-b" | "--backend" => options.backend = value.clone(),
+                    _ => options.output = PathBuf::from(value),
+                }
+            }
+            flag if flag.starts_with('-') => usage_error(program, &format!("Unknown flag: {}", flag)),
+            term => options.search_terms.push(term.to_string()),
+        }
+    }
+
+    if let Some(mapping) = field_mapping {
+        options.note_type = match options.note_type.with_field_mapping(&mapping) {
+            Ok(note_type) => note_type,
+            Err(e) => usage_error(program, &e),
+        };
+    }
+
+    if options.output.as_os_str().is_empty() {
+        usage_error(program, "export requires an output file (-o <file.tsv|file.apkg>)");
+    }
+    options
+}
+
+fn parse_selection(selection: &str) -> Option<Vec<usize>> {
+    selection.split(',').map(|number| number.trim().parse().ok()).collect()
+}
+
+fn usage_error(program: &str, message: &str) -> ! {
+    eprintln!("{}", message);
+    print_usage(program);
+    std::process::exit(1);
+}
+
+fn print_usage(program: &str) {
+    eprintln!("Usage: {} [-j|-b <backend>] [--accent <file>] [--furigana <inline|ruby>] [--format <format>] [--fallback <backends>|--no-fallback] [--limit <n>] [--dict <names>] [--follow <n>] [--no-pager] [--debug] <japanese_word>", program);
+    eprintln!("       {} -i [-j] [--accent <file>] [--furigana <inline|ruby>]", program);
+    eprintln!("       {} -t [-j] [--accent <file>] [<japanese_word>]", program);
+    eprintln!("       {} -w [-j] [--accent <file>] [--furigana <inline|ruby>]", program);
+    eprintln!("       {} -s [--particles] [--edict <file>] [--furigana <inline|ruby>] [--format <format>] <japanese_sentence>", program);
+    eprintln!("       {} batch [-j] [--dict <names>] [--format <format>] [-o <file>] [--jobs <n>] [--rate <n>] [<file>|-]", program);
+    eprintln!("       {} thesaurus [--format <format>] <japanese_word>", program);
+    eprintln!("       {} config", program);
+    eprintln!("       {} history [list|search <query>|show <n> [--format <format>]|star <n>|unstar <n>|export -o <file>] [--starred]", program);
+    eprintln!("       {} export [-j] -o <file.tsv|file.apkg> [--select 1,3] [--note-type <name>] [--fields <mapping>] [--deck <name>] <japanese_word>...", program);
+    eprintln!("  -j: Use Japanese monolingual dictionary (Weblio)");
+    eprintln!("  -b, --backend <name>: Dictionary backend: wwwjdic (default), jisho.org, weblio, ejje (Weblio English-Japanese), goo, kotobank, offline or thesaurus");
+    eprintln!("  --fallback <backends>: Backends to try in order when the first fails or finds nothing, eg: jisho.org,weblio");
+    eprintln!("  --debug: Report lines and pages a backend could not parse (also $JISHO_DEBUG)");
+    eprintln!("  --no-fallback: Only ask the one backend, even if the config sets a fallback list");
+    eprintln!("  --limit <n>: Show at most n entries per lookup");
+    eprintln!("  --dict <names>: Only show Weblio entries from these dictionaries, eg: 大辞泉,大辞林");
+    eprintln!("  -i: Interactive mode, type words at a prompt (:help for commands)");
+    eprintln!("  -t, --tui: Full-screen browser for results (/ to search, 1-9 to follow synonyms)");
+    eprintln!("  --accent <file>: Pitch accent dictionary (default: $JISHO_ACCENT_DICT or ~/.local/share/jisho/accents.txt)");
+    eprintln!("  -w, --watch: Look up Japanese text as it is copied to the clipboard (provider: $JISHO_CLIPBOARD_CMD, wl-paste, xclip or xsel)");
+    eprintln!("  -s, --segment: Split a sentence into words and look each up in the offline dictionary");
+    eprintln!("  --particles: Include particles in the --segment glossary");
+    eprintln!("  --edict <file>: Offline EDICT2 dictionary (default: $JISHO_EDICT or ~/.local/share/jisho/edict2)");
+    eprintln!("  --format <format>: Output as text (default), markdown, html (standalone page with ruby readings), json or tsv");
+    eprintln!("  --theme <dark|light|file>: Color theme (default: $JISHO_THEME or dark); NO_COLOR turns color off");
+    eprintln!("  --furigana <inline|ruby>: Show readings over the kanji they belong to");
+    eprintln!("  --no-pager: Don't pipe output taller than the terminal through $PAGER (default: less)");
+    eprintln!("  --follow <n>: Look up cross-reference [n] of the result and jump to the referenced sense");
+    eprintln!("  export: Write the looked up entries to an Anki TSV (appended) or .apkg package");
+    eprintln!("    --select 1,3: Only export these entries, numbered in display order");
+    eprintln!("    --note-type <name>: Anki note type (default: Jisho)");
+    eprintln!("    --fields <mapping>: Note fields, eg: Front=word,Back=definitions");
+    eprintln!("                        (fields: word, reading, pos, definitions, synonyms, source)");
+    eprintln!("    --deck <name>: Deck for .apkg packages (default: Jisho)");
+    eprintln!("  batch: Look up newline-separated terms from a file or stdin and list the ones with no match");
+    eprintln!("    --jobs <n>: Lookups to run at once (default: 4)");
+    eprintln!("    --rate <n>: Requests per second to the backend (default: 2, 0 for no limit)");
+    eprintln!("  thesaurus: Show Weblio 類語辞典 synonyms grouped by nuance");
+    eprintln!("  config: Print the effective settings from ~/.config/jisho/config ($JISHO_CONFIG) and the environment");
+    eprintln!("  history: List, search, star and re-show past lookups without a network call");
+    eprintln!("    export -o <file>: Write lookups as Anki TSV/.apkg (same flags as export) or .jsonl");
+}
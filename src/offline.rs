@@ -0,0 +1,193 @@
+use crate::{Dictionary, DictionaryEntry, DictionaryResult, data_dir};
+use crate::deinflect::{Deinflection, VS, deinflect};
+use crate::jisho_search::parse_entry;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+pub const SOURCE: &str = "offline";
+
+/// A local EDICT/EDICT2 format dictionary file (UTF-8), indexed by every written form and reading
+pub struct OfflineDictionary {
+    lines: Vec<String>,
+    index: HashMap<String, Vec<usize>>,
+    longest_key: usize,
+}
+
+impl OfflineDictionary {
+    pub fn load(path: &Path) -> DictionaryResult<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("could not read offline dictionary {}: {}", path.display(), e))?;
+        Ok(Self::parse(&contents))
+    }
+
+    /// Load the dictionary from `default_path`
+    pub fn load_default() -> DictionaryResult<Self> {
+        let path = default_path().ok_or("could not determine the offline dictionary location (HOME is not set)")?;
+        Self::load(&path)
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let mut lines = Vec::new();
+        let mut index: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut longest_key = 0;
+
+        for line in contents.lines() {
+            // The first line of EDICT files is a header entry
+            if line.trim().is_empty() || line.starts_with("　？？？") {
+                continue;
+            }
+
+            for key in entry_keys(line) {
+                longest_key = longest_key.max(key.chars().count());
+                let positions = index.entry(key).or_default();
+                if positions.last() != Some(&lines.len()) {
+                    positions.push(lines.len());
+                }
+            }
+            lines.push(line.to_string());
+        }
+
+        Self { lines, index, longest_key }
+    }
+
+    /// Length in characters of the longest headword or reading
+    pub fn longest_key(&self) -> usize {
+        self.longest_key
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.index.contains_key(word)
+    }
+
+    /// Entries whose written form or reading is exactly `word`
+    pub fn lookup_exact(&self, word: &str) -> Vec<DictionaryEntry> {
+        self.index.get(word)
+            .map(|positions| positions.iter().map(|&i| parse_line(&self.lines[i])).collect())
+            .unwrap_or_default()
+    }
+
+    /// Entries for a possible dictionary form, keeping only those whose part of speech fits
+    /// the inflection. Suru-verb forms also match the noun they are built on (勉強する -> 勉強).
+    pub fn lookup_deinflected(&self, deinflection: &Deinflection) -> Vec<DictionaryEntry> {
+        let mut entries: Vec<DictionaryEntry> = self.lookup_exact(&deinflection.word)
+            .into_iter()
+            .filter(|entry| deinflection.matches(entry))
+            .collect();
+
+        if deinflection.word_type & VS != 0
+            && let Some(noun) = deinflection.word.strip_suffix("する")
+            && !noun.is_empty()
+        {
+            entries.extend(self.lookup_exact(noun).into_iter().filter(|entry| entry.part_of_speech.contains("vs")));
+        }
+
+        entries
+    }
+}
+
+impl Dictionary for OfflineDictionary {
+    fn name(&self) -> &'static str {
+        SOURCE
+    }
+
+    /// Exact matches, or failing that the dictionary forms of an inflected word
+    fn lookup(&self, term: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
+        for deinflection in deinflect(term) {
+            let entries = self.lookup_deinflected(&deinflection);
+            if !entries.is_empty() {
+                return Ok(entries);
+            }
+        }
+        Ok(Vec::new())
+    }
+}
+
+static CONFIGURED_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `path` as the default location, e.g. from the config file
+pub fn set_default_path(path: PathBuf) {
+    let _ = CONFIGURED_PATH.set(path);
+}
+
+/// Offline dictionary location: the path given to `set_default_path`, `$JISHO_EDICT`,
+/// else `edict2` in the data directory
+pub fn default_path() -> Option<PathBuf> {
+    if let Some(path) = CONFIGURED_PATH.get() {
+        return Some(path.clone());
+    }
+    if let Some(path) = env::var_os("JISHO_EDICT").filter(|path| !path.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    data_dir().map(|dir| dir.join("edict2"))
+}
+
+fn parse_line(line: &str) -> DictionaryEntry {
+    // EDICT2 lines end with an entry sequence number like /EntL1234567X/
+    let line = match line.rfind("/EntL") {
+        Some(position) => &line[..position + 1],
+        None => line,
+    };
+
+    parse_entry(line).with_source(SOURCE)
+}
+
+/// Written forms and readings of an EDICT line, with tags like (P) removed
+fn entry_keys(line: &str) -> Vec<String> {
+    let head = line.split(" /").next().unwrap_or("");
+    let (words, readings) = match (head.find('['), head.rfind(']')) {
+        (Some(open), Some(close)) if open < close => (&head[..open], &head[open + 1..close]),
+        _ => (head, ""),
+    };
+
+    words.split(';')
+        .chain(readings.split(';'))
+        .map(|form| form.split('(').next().unwrap_or("").trim().to_string())
+        .filter(|form| !form.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = concat!("　？？？？ /EDICT, EDICT_SUB(P), EDICT2/Copyright Electronic Dictionary Research & Development Group - 2024/\n", "\
+食べる(P);喰べる(oK) [たべる(P)] /(v1,vt) (1) to eat/(2) to live on/(P)/EntL1358280X/
+行く(P);逝く;往く(oK) [いく(P);ゆく(P)] /(v5k-s,vi) (1) to go/(2) to move (towards)/(P)/EntL1578850X/
+勉強(P) [べんきょう(P)] /(n,vs,vt) (1) study/(2) diligence/(P)/EntL1509570X/
+");
+
+    #[test]
+    fn indexes_every_form_and_reading() {
+        let dictionary = OfflineDictionary::parse(SAMPLE);
+        for key in ["食べる", "喰べる", "たべる", "行く", "逝く", "往く", "いく", "ゆく", "勉強", "べんきょう"] {
+            assert!(dictionary.contains(key), "{}", key);
+        }
+        assert!(!dictionary.contains("食べる(P)"));
+        assert!(!dictionary.contains("　？？？？"));
+        assert_eq!(dictionary.longest_key(), 5);
+    }
+
+    #[test]
+    fn parses_lines_without_the_entry_number() {
+        let dictionary = OfflineDictionary::parse(SAMPLE);
+        let entries = dictionary.lookup_exact("たべる");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].part_of_speech, "(v1,vt)");
+        assert_eq!(entries[0].definitions, ["to eat", "to live on"]);
+        assert_eq!(entries[0].source, SOURCE);
+        assert!(entries.iter().all(|entry| !entry.definitions.concat().contains("EntL")));
+    }
+
+    #[test]
+    fn looks_up_inflected_words() {
+        let dictionary = OfflineDictionary::parse(SAMPLE);
+        let entries = dictionary.lookup("食べなかった").unwrap();
+        assert_eq!(entries[0].headword(), "食べる");
+        let entries = dictionary.lookup("勉強しました").unwrap();
+        assert_eq!(entries[0].headword(), "勉強");
+        assert!(dictionary.lookup("食べ物").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,47 @@
+use ratatui::crossterm::terminal;
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Print `text`, through `$PAGER` (default `less`) when it is taller than the terminal.
+/// Colors are kept: `less` is run with `LESS=FRX` unless `$LESS` is already set.
+pub fn print(text: &str, enabled: bool) {
+    if enabled && needs_pager(text) && page(text).is_ok() {
+        return;
+    }
+
+    // A closed pipe (e.g. `jisho ... | head`) is not an error worth reporting
+    let _ = writeln!(io::stdout().lock(), "{}", text);
+}
+
+fn needs_pager(text: &str) -> bool {
+    if !io::stdout().is_terminal() {
+        return false;
+    }
+    match terminal::size() {
+        Ok((_, rows)) => text.lines().count() >= rows as usize,
+        Err(_) => false,
+    }
+}
+
+fn page(text: &str) -> io::Result<()> {
+    let pager = env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+    let mut parts = pager.split_whitespace();
+    let Some(program) = parts.next().filter(|program| *program != "cat") else {
+        return Err(io::ErrorKind::NotFound.into());
+    };
+
+    let mut command = Command::new(program);
+    command.args(parts).stdin(Stdio::piped());
+    if env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+
+    let mut child = command.spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Quitting the pager early closes the pipe, which is fine
+        let _ = writeln!(stdin, "{}", text);
+    }
+    child.wait()?;
+    Ok(())
+}
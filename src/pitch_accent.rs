@@ -0,0 +1,216 @@
+use crate::{DictionaryEntry, DictionaryResult, data_dir};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SMALL_KANA: &str = "ゃゅょぁぃぅぇぉゎャュョァィゥェォヮ";
+const OVERLINE: char = '\u{0305}';
+const DOWNSTEP: char = 'ꜜ';
+
+/// Accent patterns keyed by (word, reading), loaded from a local accent dictionary.
+///
+/// Each line of the file is `word<TAB>reading<TAB>patterns`, where patterns is a
+/// comma separated list of downstep positions (0 = heiban), e.g. `入る<TAB>はいる<TAB>1`.
+/// Kana-only words may omit the reading column.
+pub struct AccentDictionary {
+    entries: HashMap<(String, String), Vec<u8>>,
+}
+
+impl AccentDictionary {
+    pub fn load(path: &Path) -> DictionaryResult<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("could not read accent dictionary {}: {}", path.display(), e))?;
+        Ok(Self::parse(&contents))
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let mut entries = HashMap::new();
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let columns: Vec<&str> = line.split('\t').collect();
+            let (word, reading, patterns) = match columns.as_slice() {
+                [word, patterns] => (*word, *word, *patterns),
+                [word, reading, patterns, ..] => (*word, *reading, *patterns),
+                _ => continue,
+            };
+
+            let patterns = parse_patterns(patterns);
+            if !patterns.is_empty() {
+                entries.insert((word.to_string(), reading.to_string()), patterns);
+            }
+        }
+
+        Self { entries }
+    }
+
+    pub fn lookup(&self, word: &str, reading: &str) -> Option<&[u8]> {
+        self.entries
+            .get(&(word.to_string(), reading.to_string()))
+            .or_else(|| self.entries.get(&(reading.to_string(), reading.to_string())))
+            .map(|patterns| patterns.as_slice())
+    }
+
+    /// Fill in `pitch_accent` for every entry found in the dictionary
+    pub fn annotate(&self, entries: &mut [DictionaryEntry]) {
+        for entry in entries.iter_mut() {
+            if let Some(patterns) = self.lookup(&entry.headword(), &entry.reading()) {
+                entry.pitch_accent = patterns.to_vec();
+            }
+        }
+    }
+}
+
+/// Accent dictionary location: `$JISHO_ACCENT_DICT`, else `accents.txt` in the data directory
+pub fn default_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("JISHO_ACCENT_DICT").filter(|path| !path.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    data_dir().map(|dir| dir.join("accents.txt"))
+}
+
+fn parse_patterns(patterns: &str) -> Vec<u8> {
+    // Entries like "(名)0,(副)1" carry part-of-speech notes; keep only the digits
+    patterns
+        .split([',', '，'])
+        .filter_map(|pattern| {
+            let digits: String = pattern.chars().filter(|c| c.is_ascii_digit()).collect();
+            digits.parse().ok()
+        })
+        .collect()
+}
+
+/// Split a kana reading into morae, attaching small kana to the preceding character
+pub fn morae(reading: &str) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+
+    for ch in reading.chars() {
+        match result.last_mut() {
+            Some(last) if SMALL_KANA.contains(ch) => last.push(ch),
+            _ => result.push(ch.to_string()),
+        }
+    }
+
+    result
+}
+
+pub fn pattern_name(pattern: u8, mora_count: usize) -> &'static str {
+    match pattern {
+        0 => "平板",
+        1 => "頭高",
+        n if n as usize == mora_count => "尾高",
+        _ => "中高",
+    }
+}
+
+/// Render a reading with an overline over high morae and a downstep marker after the accent nucleus,
+/// e.g. `は̅ꜜいる` for はいる with pattern 1
+pub fn render_reading(reading: &str, pattern: u8) -> String {
+    let morae = morae(reading);
+    let nucleus = pattern as usize;
+    let mut result = String::new();
+
+    for (i, mora) in morae.iter().enumerate() {
+        let position = i + 1;
+        let high = match nucleus {
+            0 => position > 1,
+            1 => position == 1,
+            n => position > 1 && position <= n,
+        };
+
+        for ch in mora.chars() {
+            result.push(ch);
+            if high {
+                result.push(OVERLINE);
+            }
+        }
+
+        if position == nucleus {
+            result.push(DOWNSTEP);
+        }
+    }
+
+    result
+}
+
+/// Accent summary for display, e.g. `は̅ꜜいる [1 頭高]`
+pub fn describe(reading: &str, pattern: u8) -> String {
+    format!(
+        "{} [{} {}]",
+        render_reading(reading, pattern),
+        pattern,
+        pattern_name(pattern, morae(reading).len())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_morae() {
+        assert_eq!(morae("きょう"), ["きょ", "う"]);
+        assert_eq!(morae("しゅっちょう"), ["しゅ", "っ", "ちょ", "う"]);
+        assert_eq!(morae("コーヒー"), ["コ", "ー", "ヒ", "ー"]);
+        assert_eq!(morae("ティー"), ["ティ", "ー"]);
+    }
+
+    #[test]
+    fn renders_heiban() {
+        // さくら is low on the first mora and high to the end with no downstep
+        assert_eq!(render_reading("さくら", 0), "さく\u{305}ら\u{305}");
+        assert_eq!(pattern_name(0, 3), "平板");
+    }
+
+    #[test]
+    fn renders_atamadaka() {
+        assert_eq!(render_reading("はいる", 1), "は\u{305}ꜜいる");
+        assert_eq!(describe("はいる", 1), "は\u{305}ꜜいる [1 頭高]");
+    }
+
+    #[test]
+    fn renders_odaka() {
+        // The downstep falls after the last mora, on a following particle
+        assert_eq!(render_reading("おとこ", 3), "おと\u{305}こ\u{305}ꜜ");
+        assert_eq!(pattern_name(3, 3), "尾高");
+    }
+
+    #[test]
+    fn renders_nakadaka_with_small_kana() {
+        assert_eq!(render_reading("ひこうき", 2), "ひこ\u{305}ꜜうき");
+        assert_eq!(render_reading("きょうしつ", 2), "きょう\u{305}ꜜしつ");
+        assert_eq!(pattern_name(2, 4), "中高");
+    }
+
+    #[test]
+    fn parses_dictionary_lines() {
+        let dictionary = AccentDictionary::parse(
+            "# word\treading\tpatterns\n\
+             入る\tはいる\t1\n\
+             今日\tきょう\t1\n\
+             さくら\t0\n\
+             日本\tにほん\t(名)2,(名)3\n\
+             \n\
+             broken line\n",
+        );
+        assert_eq!(dictionary.lookup("入る", "はいる"), Some(&[1][..]));
+        assert_eq!(dictionary.lookup("さくら", "さくら"), Some(&[0][..]));
+        assert_eq!(dictionary.lookup("桜", "さくら"), Some(&[0][..]));
+        assert_eq!(dictionary.lookup("日本", "にほん"), Some(&[2, 3][..]));
+        assert_eq!(dictionary.lookup("入る", "いる"), None);
+        assert_eq!(dictionary.entries.len(), 4);
+    }
+
+    #[test]
+    fn annotates_entries() {
+        let dictionary = AccentDictionary::parse("入る\tはいる\t1\n");
+        let mut entries = vec![DictionaryEntry::new("入る [はいる]".to_string(), "(v5r)".to_string(), Vec::new())];
+        dictionary.annotate(&mut entries);
+        assert_eq!(entries[0].pitch_accent, [1]);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::DictionaryEntry;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReferenceKind {
+    Synonym,
+    Antonym,
+    See,
+}
+
+impl ReferenceKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReferenceKind::Synonym => "syn",
+            ReferenceKind::Antonym => "ant",
+            ReferenceKind::See => "see",
+        }
+    }
+}
+
+/// A pointer from an entry to another headword, e.g. `(ant: 出る・1)` in EDICT or a Weblio synonym link
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reference {
+    pub kind: ReferenceKind,
+    pub term: String,
+    /// Reading given with the reference, if any
+    pub reading: Option<String>,
+    /// Sense of the referenced entry (1-based)
+    pub sense: Option<usize>,
+}
+
+impl Reference {
+    pub fn new(kind: ReferenceKind, term: String) -> Self {
+        Self { kind, term, reading: None, sense: None }
+    }
+
+    /// Parse an EDICT reference target like `出る・1`, `入る・はいる・1` or `入る`
+    pub fn parse_edict(kind: ReferenceKind, target: &str) -> Option<Self> {
+        let mut parts: Vec<&str> = target.trim().split('・').map(str::trim).collect();
+
+        let sense = match parts.last().map(|last| last.parse::<usize>()) {
+            Some(Ok(sense)) if parts.len() > 1 => {
+                parts.pop();
+                Some(sense)
+            }
+            _ => None,
+        };
+
+        let term = parts.first().filter(|term| !term.is_empty())?.to_string();
+        let reading = parts.get(1).map(|reading| reading.to_string());
+        Some(Self { kind, term, reading, sense })
+    }
+}
+
+/// Find cross-references like `(ant: 出る・1)` or `(See 入る,入れる)` in EDICT definition text
+pub fn parse_edict_references(text: &str) -> Vec<Reference> {
+    let mut references = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('(') {
+        let Some(length) = rest[start..].find(')') else {
+            break;
+        };
+        let inside = &rest[start + 1..start + length];
+        rest = &rest[start + length + 1..];
+
+        let (kind, targets) = if let Some(targets) = inside.strip_prefix("ant:") {
+            (ReferenceKind::Antonym, targets)
+        } else if let Some(targets) = inside.strip_prefix("See ").or_else(|| inside.strip_prefix("see ")) {
+            (ReferenceKind::See, targets)
+        } else {
+            continue;
+        };
+
+        references.extend(targets.split(',').filter_map(|target| Reference::parse_edict(kind, target)));
+    }
+
+    references
+}
+
+/// All references of the given entries, in display order
+pub fn all_references(entries: &[DictionaryEntry]) -> Vec<&Reference> {
+    entries.iter().flat_map(|entry| entry.references.iter()).collect()
+}
+
+/// Narrow a lookup for a referenced term down to the entries that are actually that word
+pub fn referenced_entries(entries: Vec<DictionaryEntry>, reference: &Reference) -> Vec<DictionaryEntry> {
+    let matching: Vec<DictionaryEntry> = entries.iter()
+        .filter(|entry| {
+            let word_matches = entry.headword() == reference.term || entry.reading() == reference.term;
+            let reading_matches = reference.reading.as_ref().is_none_or(|reading| entry.reading() == *reading);
+            word_matches && reading_matches
+        })
+        .cloned()
+        .collect();
+
+    if matching.is_empty() {
+        entries
+    } else {
+        matching
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jisho_search::parse_entry;
+
+    fn reference(kind: ReferenceKind, term: &str, reading: Option<&str>, sense: Option<usize>) -> Reference {
+        Reference { kind, term: term.to_string(), reading: reading.map(str::to_string), sense }
+    }
+
+    #[test]
+    fn parses_antonym_with_sense() {
+        assert_eq!(
+            parse_edict_references("(ant: 出る・1) to enter/to come in"),
+            [reference(ReferenceKind::Antonym, "出る", None, Some(1))],
+        );
+    }
+
+    #[test]
+    fn parses_see_with_several_targets() {
+        assert_eq!(
+            parse_edict_references("(See 入る,入れる・いれる・2) to put in"),
+            [
+                reference(ReferenceKind::See, "入る", None, None),
+                reference(ReferenceKind::See, "入れる", Some("いれる"), Some(2)),
+            ],
+        );
+    }
+
+    #[test]
+    fn ignores_other_parentheses() {
+        assert!(parse_edict_references("(v5r,vi) (1) to join (a club, company, etc.)").is_empty());
+        assert!(parse_edict_references("(ant: 出る").is_empty());
+    }
+
+    #[test]
+    fn keeps_a_numeric_term_without_a_sense() {
+        assert_eq!(Reference::parse_edict(ReferenceKind::See, "１"), Some(reference(ReferenceKind::See, "１", None, None)));
+        assert_eq!(Reference::parse_edict(ReferenceKind::See, " "), None);
+    }
+
+    #[test]
+    fn collects_references_from_edict_glosses() {
+        let entry = parse_entry("入る(P);這入る(rK) [はいる] /(v5r,vi) (1) (ant: 出る・1) to enter/to come in/(2) (See 入れる) to join/(P)/");
+        assert_eq!(
+            entry.references,
+            [
+                reference(ReferenceKind::Antonym, "出る", None, Some(1)),
+                reference(ReferenceKind::See, "入れる", None, None),
+            ],
+        );
+    }
+}
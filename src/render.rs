@@ -0,0 +1,480 @@
+use crate::DictionaryEntry;
+use crate::anki::escape_html;
+use crate::format::{FormatOptions, format_entries, metadata_line, starts_dictionary_group};
+use crate::furigana::{self, FuriganaStyle};
+use crate::pitch_accent;
+use crate::reference::ReferenceKind;
+use crate::sense::Sense;
+use crate::theme;
+use serde::Serialize;
+use std::str::FromStr;
+
+/// A titled group of entries, e.g. the result for one term of a batch
+pub struct Section<'a> {
+    pub title: &'a str,
+    pub entries: &'a [DictionaryEntry],
+}
+
+/// Turns lookup results into a document of some output format
+pub trait Renderer {
+    /// The result of a single lookup
+    fn render(&self, entries: &[DictionaryEntry], options: &FormatOptions) -> String;
+
+    /// Several results in one document, each under its title
+    fn render_sections(&self, sections: &[Section], options: &FormatOptions) -> String;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Colored terminal output
+    #[default]
+    Text,
+    Markdown,
+    /// Standalone HTML page with ruby readings
+    Html,
+    Json,
+    Tsv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "html" => Ok(OutputFormat::Html),
+            "json" => Ok(OutputFormat::Json),
+            "tsv" => Ok(OutputFormat::Tsv),
+            other => Err(format!("unknown output format: {} (expected text, markdown, html, json or tsv)", other)),
+        }
+    }
+}
+
+impl OutputFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Markdown => "markdown",
+            OutputFormat::Html => "html",
+            OutputFormat::Json => "json",
+            OutputFormat::Tsv => "tsv",
+        }
+    }
+
+    pub fn renderer(&self) -> Box<dyn Renderer> {
+        match self {
+            OutputFormat::Text => Box::new(TextRenderer),
+            OutputFormat::Markdown => Box::new(MarkdownRenderer),
+            OutputFormat::Html => Box::new(HtmlRenderer),
+            OutputFormat::Json => Box::new(JsonRenderer),
+            OutputFormat::Tsv => Box::new(TsvRenderer),
+        }
+    }
+}
+
+pub struct TextRenderer;
+
+impl Renderer for TextRenderer {
+    fn render(&self, entries: &[DictionaryEntry], options: &FormatOptions) -> String {
+        format_entries(entries, options)
+    }
+
+    fn render_sections(&self, sections: &[Section], options: &FormatOptions) -> String {
+        sections.iter()
+            .map(|section| format!("{}\n{}", theme::current().heading.paint(&format!("── {} ──", section.title)), format_entries(section.entries, options)))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+pub struct MarkdownRenderer;
+
+impl Renderer for MarkdownRenderer {
+    fn render(&self, entries: &[DictionaryEntry], options: &FormatOptions) -> String {
+        markdown_entries(entries, options, "##")
+    }
+
+    fn render_sections(&self, sections: &[Section], options: &FormatOptions) -> String {
+        sections.iter()
+            .map(|section| format!("## {}\n\n{}", section.title, markdown_entries(section.entries, options, "###")))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+fn markdown_entries(entries: &[DictionaryEntry], options: &FormatOptions, heading: &str) -> String {
+    if entries.is_empty() {
+        return "No definitions found.".to_string();
+    }
+    entries.iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let formatted = markdown_entry(entry, options, heading);
+            if starts_dictionary_group(entries, i) {
+                format!("*{}*\n\n{}", entry.dictionary, formatted)
+            } else {
+                formatted
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn markdown_entry(entry: &DictionaryEntry, options: &FormatOptions, heading: &str) -> String {
+    let mut lines = Vec::new();
+
+    let reading = entry.reading();
+    let headword = match options.furigana {
+        Some(FuriganaStyle::Inline) => furigana::to_inline(&furigana::align(&entry.headword(), &reading)),
+        // Ruby annotations are plain HTML, which most Markdown renderers pass through
+        Some(FuriganaStyle::Ruby) => html_ruby(entry),
+        None if reading != entry.headword() => format!("{} ({})", entry.headword(), reading),
+        None => entry.headword(),
+    };
+    lines.push(format!("{} {}", heading, headword));
+    lines.push(String::new());
+
+    let metadata = entry.metadata();
+    if !metadata.is_empty() {
+        lines.push(format!("*{}*", metadata_line(&metadata)));
+        lines.push(String::new());
+    }
+
+    if !entry.pitch_accent.is_empty() {
+        lines.push(format!("Accent: {}", accent_descriptions(entry)));
+        lines.push(String::new());
+    }
+    if !entry.part_of_speech.is_empty() {
+        lines.push(format!("*{}*", entry.part_of_speech));
+        lines.push(String::new());
+    }
+
+    if entry.senses.is_empty() {
+        for (i, definition) in entry.definitions.iter().enumerate() {
+            let definition = definition.replace('\n', " ");
+            if options.highlight_sense == Some(i + 1) {
+                lines.push(format!("{}. **{}**", i + 1, definition));
+            } else {
+                lines.push(format!("{}. {}", i + 1, definition));
+            }
+        }
+    } else {
+        for (i, sense) in entry.senses.iter().enumerate() {
+            let text = markdown_sense_text(sense);
+            if options.highlight_sense == Some(i + 1) {
+                lines.push(format!("{}. **{}**", i + 1, text));
+            } else {
+                lines.push(format!("{}. {}", i + 1, text));
+            }
+            push_markdown_subsenses(&mut lines, sense, "   ");
+        }
+    }
+
+    if !entry.example_sentences.is_empty() {
+        lines.push(String::new());
+        lines.push("**例文:**".to_string());
+        lines.push(String::new());
+        for example in &entry.example_sentences {
+            if example.translation.is_empty() {
+                lines.push(format!("- {}", example.text));
+            } else {
+                lines.push(format!("- {} — *{}*", example.text, example.translation));
+            }
+        }
+    }
+
+    for (label, targets) in reference_groups(entry) {
+        lines.push(String::new());
+        lines.push(format!("**{}** {}", label, targets.join(", ")));
+    }
+
+    lines.join("\n").trim_end().to_string()
+}
+
+/// Sub-senses as nested lists, each level indented under the item above it
+fn push_markdown_subsenses(lines: &mut Vec<String>, sense: &Sense, indent: &str) {
+    for subsense in &sense.subsenses {
+        lines.push(format!("{}- {} {}", indent, subsense.label, markdown_sense_text(subsense)));
+        push_markdown_subsenses(lines, subsense, &format!("{}  ", indent));
+    }
+}
+
+/// Sense text with its examples in italics
+fn markdown_sense_text(sense: &Sense) -> String {
+    let mut text = sense.text.replace('\n', " ");
+    if !sense.part_of_speech.is_empty() {
+        text = format!("*{}* {}", sense.part_of_speech, text);
+    }
+    if sense.examples.is_empty() {
+        text
+    } else {
+        format!("{} *{}*", text, sense.examples.concat()).trim_start().to_string()
+    }
+}
+
+pub struct HtmlRenderer;
+
+const HTML_STYLE: &str = "\
+body { font-family: sans-serif; max-width: 48em; margin: 2em auto; line-height: 1.5; }
+.entry { margin-bottom: 1.5em; }
+.headword { font-size: 1.5em; margin: 0; }
+.pos { color: #8a6d00; font-style: italic; }
+.accent { color: #2a5db0; }
+.highlight { font-weight: bold; background: #fff3b0; }
+.references { color: #555; }
+.examples { color: #555; }
+.example-sentences dd { color: #555; margin-bottom: 0.5em; }
+.metadata { color: #555; font-size: 0.9em; }
+.subsenses { list-style: none; padding-left: 1em; }
+.dictionary { color: #555; border-bottom: 1px solid #ccc; margin: 1em 0 0.5em; }";
+
+impl HtmlRenderer {
+    fn document(body: &str) -> String {
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"ja\">\n<head>\n<meta charset=\"utf-8\">\n<title>jisho</title>\n<style>\n{}\n</style>\n</head>\n<body>\n{}\n</body>\n</html>\n",
+            HTML_STYLE, body
+        )
+    }
+
+    fn entries(entries: &[DictionaryEntry], options: &FormatOptions) -> String {
+        if entries.is_empty() {
+            return "<p>No definitions found.</p>".to_string();
+        }
+        entries.iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let formatted = html_entry(entry, options);
+                if starts_dictionary_group(entries, i) {
+                    format!("<div class=\"dictionary\">{}</div>\n{}", escape_html(&entry.dictionary), formatted)
+                } else {
+                    formatted
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Renderer for HtmlRenderer {
+    fn render(&self, entries: &[DictionaryEntry], options: &FormatOptions) -> String {
+        Self::document(&Self::entries(entries, options))
+    }
+
+    fn render_sections(&self, sections: &[Section], options: &FormatOptions) -> String {
+        let body: Vec<String> = sections.iter()
+            .map(|section| format!("<section>\n<h2>{}</h2>\n{}\n</section>", escape_html(section.title), Self::entries(section.entries, options)))
+            .collect();
+        Self::document(&body.join("\n"))
+    }
+}
+
+fn html_entry(entry: &DictionaryEntry, options: &FormatOptions) -> String {
+    let mut html = String::from("<div class=\"entry\">\n");
+    html.push_str(&format!("<h3 class=\"headword\">{}</h3>\n", html_ruby(entry)));
+
+    let metadata = entry.metadata();
+    if !metadata.is_empty() {
+        html.push_str(&format!("<div class=\"metadata\">{}</div>\n", escape_html(&metadata_line(&metadata))));
+    }
+    if !entry.pitch_accent.is_empty() {
+        html.push_str(&format!("<div class=\"accent\">{}</div>\n", escape_html(&accent_descriptions(entry))));
+    }
+    if !entry.part_of_speech.is_empty() {
+        html.push_str(&format!("<div class=\"pos\">{}</div>\n", escape_html(&entry.part_of_speech)));
+    }
+
+    if !entry.senses.is_empty() {
+        html.push_str("<ol>\n");
+        for (i, sense) in entry.senses.iter().enumerate() {
+            let class = if options.highlight_sense == Some(i + 1) { " class=\"highlight\"" } else { "" };
+            html.push_str(&format!("<li{}>{}</li>\n", class, html_sense(sense)));
+        }
+        html.push_str("</ol>\n");
+    } else if !entry.definitions.is_empty() {
+        html.push_str("<ol>\n");
+        for (i, definition) in entry.definitions.iter().enumerate() {
+            let class = if options.highlight_sense == Some(i + 1) { " class=\"highlight\"" } else { "" };
+            html.push_str(&format!("<li{}>{}</li>\n", class, escape_html(definition)));
+        }
+        html.push_str("</ol>\n");
+    }
+
+    if !entry.example_sentences.is_empty() {
+        html.push_str("<dl class=\"example-sentences\">\n");
+        for example in &entry.example_sentences {
+            html.push_str(&format!("<dt>{}</dt><dd>{}</dd>\n", escape_html(&example.text), escape_html(&example.translation)));
+        }
+        html.push_str("</dl>\n");
+    }
+
+    for (label, targets) in reference_groups(entry) {
+        let targets: Vec<String> = targets.iter().map(|target| escape_html(target)).collect();
+        html.push_str(&format!("<div class=\"references\">{} {}</div>\n", label, targets.join(", ")));
+    }
+
+    html.push_str("</div>");
+    html
+}
+
+/// Sense text, examples and a list of its sub-senses
+fn html_sense(sense: &Sense) -> String {
+    let mut html = String::new();
+    if !sense.part_of_speech.is_empty() {
+        html.push_str(&format!("<span class=\"pos\">{}</span> ", escape_html(&sense.part_of_speech)));
+    }
+    html.push_str(&escape_html(&sense.text));
+    if !sense.examples.is_empty() {
+        html.push_str(&format!("<div class=\"examples\">{}</div>", escape_html(&sense.examples.concat())));
+    }
+    if !sense.subsenses.is_empty() {
+        html.push_str("<ul class=\"subsenses\">");
+        for subsense in &sense.subsenses {
+            html.push_str(&format!("<li><span class=\"label\">{}</span> {}</li>", escape_html(&subsense.label), html_sense(subsense)));
+        }
+        html.push_str("</ul>");
+    }
+    html
+}
+
+/// Headword with its reading as `<ruby>` annotations over the kanji they belong to
+fn html_ruby(entry: &DictionaryEntry) -> String {
+    furigana::align(&entry.headword(), &entry.reading())
+        .iter()
+        .map(|segment| match &segment.reading {
+            Some(reading) => format!("<ruby>{}<rt>{}</rt></ruby>", escape_html(&segment.text), escape_html(reading)),
+            None => escape_html(&segment.text),
+        })
+        .collect()
+}
+
+pub struct JsonRenderer;
+
+impl Renderer for JsonRenderer {
+    fn render(&self, entries: &[DictionaryEntry], _options: &FormatOptions) -> String {
+        serde_json::to_string_pretty(entries).unwrap_or_default()
+    }
+
+    fn render_sections(&self, sections: &[Section], _options: &FormatOptions) -> String {
+        #[derive(Serialize)]
+        struct JsonSection<'a> {
+            term: &'a str,
+            entries: &'a [DictionaryEntry],
+        }
+
+        let sections: Vec<JsonSection> = sections.iter()
+            .map(|section| JsonSection { term: section.title, entries: section.entries })
+            .collect();
+        serde_json::to_string_pretty(&sections).unwrap_or_default()
+    }
+}
+
+/// One row per entry, with a header line
+pub struct TsvRenderer;
+
+const TSV_HEADER: &str = "term\tword\treading\tpos\tdefinitions\tsource\tdictionary";
+
+impl Renderer for TsvRenderer {
+    fn render(&self, entries: &[DictionaryEntry], _options: &FormatOptions) -> String {
+        let mut output = String::from(TSV_HEADER);
+        for entry in entries {
+            output.push('\n');
+            output.push_str(&tsv_row(&entry.headword(), entry));
+        }
+        output
+    }
+
+    fn render_sections(&self, sections: &[Section], _options: &FormatOptions) -> String {
+        let mut output = String::from(TSV_HEADER);
+        for section in sections {
+            for entry in section.entries {
+                output.push('\n');
+                output.push_str(&tsv_row(section.title, entry));
+            }
+        }
+        output
+    }
+}
+
+fn tsv_row(term: &str, entry: &DictionaryEntry) -> String {
+    let fields = [
+        term.to_string(),
+        entry.headword(),
+        entry.reading(),
+        entry.part_of_speech.clone(),
+        entry.definitions.join("; "),
+        entry.source.clone(),
+        entry.dictionary.clone(),
+    ];
+    fields.iter()
+        .map(|field| field.replace(['\t', '\n'], " "))
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+fn accent_descriptions(entry: &DictionaryEntry) -> String {
+    let reading = entry.reading();
+    entry.pitch_accent.iter()
+        .map(|&pattern| pitch_accent::describe(&reading, pattern))
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+/// Synonyms and other references as labeled lists, falling back to plain synonyms
+fn reference_groups(entry: &DictionaryEntry) -> Vec<(&'static str, Vec<String>)> {
+    if entry.references.is_empty() {
+        if entry.synonyms.is_empty() {
+            return Vec::new();
+        }
+        return vec![("類語:", entry.synonyms.clone())];
+    }
+
+    let mut synonyms = Vec::new();
+    let mut others = Vec::new();
+    for reference in &entry.references {
+        let mut target = reference.term.clone();
+        if let Some(sense) = reference.sense {
+            target.push_str(&format!(" ({})", sense));
+        }
+        match reference.kind {
+            ReferenceKind::Synonym => synonyms.push(target),
+            kind => others.push(format!("{} ({})", target, kind.label())),
+        }
+    }
+
+    [("類語:", synonyms), ("参照:", others)]
+        .into_iter()
+        .filter(|(_, targets)| !targets.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sense(label: &str, text: &str, subsenses: Vec<Sense>) -> Sense {
+        Sense { label: label.to_string(), text: text.to_string(), subsenses, ..Sense::default() }
+    }
+
+    #[test]
+    fn nests_markdown_sub_senses() {
+        let senses = vec![
+            sense("１", "心に感じる。", vec![
+                sense("①", "考える。", vec![sense("㋐", "判断する。", Vec::new())]),
+                sense("②", "信じる。", Vec::new()),
+            ]),
+            sense("２", "慕う。", Vec::new()),
+        ];
+        let entry = DictionaryEntry::new("おもう【思う】".to_string(), String::new(), vec!["x".to_string()])
+            .with_senses(senses);
+        let markdown = MarkdownRenderer.render(&[entry], &FormatOptions::default());
+        let list: Vec<&str> = markdown.lines().skip_while(|line| !line.starts_with("1.")).collect();
+        assert_eq!(list, [
+            "1. 心に感じる。",
+            "   - ① 考える。",
+            "     - ㋐ 判断する。",
+            "   - ② 信じる。",
+            "2. 慕う。",
+        ]);
+    }
+}
@@ -0,0 +1,182 @@
+use crate::{BACKENDS, Dictionary, DictionaryEntry, DictionaryResult, data_dir, dictionary_by_name, is_backend};
+use crate::format::{FormatOptions, format_entries};
+use crate::reference::all_references;
+use crate::session::Session;
+use crate::theme;
+use crate::wrap;
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
+
+/// Entries shown per page of results
+const PAGE_SIZE: usize = 5;
+
+const HELP: &str = "\
+Type a word to look it up. Commands:
+  :jisho, :weblio    switch backend (:jisho is wwwjdic), :offline for the local dictionary
+  :goo, :kotobank    switch to another Japanese dictionary
+  :jisho.org         switch to the jisho.org API
+  :ejje              switch to Weblio's English-Japanese dictionary
+  :thesaurus         switch to Weblio synonyms
+  :backend           show the current backend
+  :n, :p             next / previous page of the last result
+  :show              show the last result again from the first page
+  :f <n>             follow cross-reference [n] on the current page
+  :furigana <style>  headword style: inline, ruby or off
+  :help              show this help
+  :q                 quit";
+
+struct Repl {
+    session: Session,
+    dictionary: Box<dyn Dictionary>,
+    format: FormatOptions,
+    entries: Vec<DictionaryEntry>,
+    page: usize,
+    /// Referenced sense to mark after following a reference
+    highlight_sense: Option<usize>,
+}
+
+/// Interactive prompt that keeps the session (HTTP client, cache, accent dictionary) warm between lookups
+pub fn run(session: Session, dictionary: Box<dyn Dictionary>, format: FormatOptions) -> DictionaryResult<()> {
+    let mut editor = DefaultEditor::new()?;
+    let history_path = data_dir().map(|dir| dir.join("repl_history"));
+    if let Some(path) = &history_path {
+        let _ = editor.load_history(path);
+    }
+
+    let mut repl = Repl {
+        session,
+        dictionary,
+        format,
+        entries: Vec::new(),
+        page: 0,
+        highlight_sense: None,
+    };
+
+    println!("{}", theme::current().heading.paint("jisho interactive mode, :help for commands"));
+    loop {
+        let prompt = format!("{}> ", repl.dictionary.name());
+        match editor.readline(&prompt) {
+            Ok(line) => {
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                let _ = editor.add_history_entry(line);
+                if !repl.handle(line) {
+                    break;
+                }
+            }
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    if let Some(path) = &history_path {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let _ = editor.save_history(path);
+    }
+    Ok(())
+}
+
+impl Repl {
+    /// Handle one line of input, returning false when the user quits
+    fn handle(&mut self, line: &str) -> bool {
+        let Some(command) = line.strip_prefix(':') else {
+            self.search(line);
+            return true;
+        };
+
+        let mut parts = command.split_whitespace();
+        match (parts.next().unwrap_or(""), parts.next()) {
+            ("q" | "quit" | "exit", _) => return false,
+            ("help" | "h", _) => println!("{}", HELP),
+            ("backend", _) => println!("{}", self.dictionary.name()),
+            ("n" | "next", _) => {
+                if (self.page + 1) * PAGE_SIZE < self.entries.len() {
+                    self.page += 1;
+                    self.show_page();
+                } else {
+                    println!("{}", theme::current().warning.paint("No more entries."));
+                }
+            }
+            ("p" | "prev", _) => {
+                self.page = self.page.saturating_sub(1);
+                self.show_page();
+            }
+            ("show", _) => {
+                self.page = 0;
+                self.show_page();
+            }
+            ("f" | "follow", number) => match number.and_then(|number| number.parse::<usize>().ok()) {
+                Some(number) => self.follow(number),
+                None => eprintln!("Usage: :f <reference number>"),
+            },
+            ("furigana", Some("off")) => self.format.furigana = None,
+            ("furigana", Some(style)) => match style.parse() {
+                Ok(style) => self.format.furigana = Some(style),
+                Err(e) => eprintln!("{}", e),
+            },
+            (name, _) if is_backend(name) => match dictionary_by_name(name) {
+                Ok(dictionary) => {
+                    self.dictionary = dictionary;
+                    println!("Using {}", self.dictionary.name());
+                }
+                Err(e) => eprintln!("Error: {}", e),
+            },
+            (name, _) => eprintln!("Unknown command :{} (backends: {}, :help for commands)", name, BACKENDS.join(", ")),
+        }
+
+        true
+    }
+
+    fn search(&mut self, term: &str) {
+        match self.session.lookup(self.dictionary.as_ref(), term) {
+            Ok(entries) => self.show_result(entries, None),
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+
+    fn follow(&mut self, number: usize) {
+        let references = all_references(self.current_page());
+        let Some(reference) = number.checked_sub(1).and_then(|i| references.get(i)).map(|reference| (*reference).clone()) else {
+            eprintln!("No reference [{}] on this page ({} found)", number, references.len());
+            return;
+        };
+
+        println!("→ {}", theme::current().heading.paint(&reference.term));
+        match self.session.follow(self.dictionary.as_ref(), &reference) {
+            Ok(entries) => self.show_result(entries, reference.sense),
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+
+    fn show_result(&mut self, entries: Vec<DictionaryEntry>, highlight_sense: Option<usize>) {
+        self.entries = entries;
+        self.page = 0;
+        self.highlight_sense = highlight_sense;
+        self.show_page();
+    }
+
+    fn current_page(&self) -> &[DictionaryEntry] {
+        let start = self.page * PAGE_SIZE;
+        let end = (start + PAGE_SIZE).min(self.entries.len());
+        self.entries.get(start..end).unwrap_or_default()
+    }
+
+    fn show_page(&self) {
+        let start = self.page * PAGE_SIZE;
+        let end = (start + PAGE_SIZE).min(self.entries.len());
+        // The terminal may have been resized since the last page
+        let width = wrap::terminal_width().or(self.format.width);
+        let format = FormatOptions { highlight_sense: self.highlight_sense, width, ..self.format };
+        println!("\n{}\n", format_entries(self.current_page(), &format));
+
+        if end < self.entries.len() {
+            let status = format!("[entries {}-{} of {}, :n for more]", start + 1, end, self.entries.len());
+            println!("{}", theme::current().muted.paint(&status));
+        }
+    }
+}
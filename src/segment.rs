@@ -0,0 +1,178 @@
+use crate::DictionaryEntry;
+use crate::deinflect::deinflect;
+use crate::format::FormatOptions;
+use crate::offline::OfflineDictionary;
+use crate::render::{Renderer, Section};
+
+const PARTICLES: &[&str] = &[
+    "は", "が", "を", "に", "で", "と", "も", "へ", "の", "や", "か", "ね", "よ", "な", "わ", "ぞ", "ぜ",
+    "から", "まで", "より", "けど", "けれど", "ので", "のに", "って", "とか", "など", "しか", "だけ", "ばかり",
+];
+
+/// A word found in a sentence, with the dictionary form it was matched against
+#[derive(Debug, Clone)]
+pub struct Token {
+    /// Text as it appears in the sentence
+    pub surface: String,
+    /// Dictionary form, if it differs from the surface
+    pub base: Option<String>,
+    /// Inflections undone to reach the dictionary form, outermost first
+    pub reasons: Vec<&'static str>,
+    pub entries: Vec<DictionaryEntry>,
+}
+
+impl Token {
+    pub fn is_particle(&self) -> bool {
+        PARTICLES.contains(&self.surface.as_str())
+    }
+
+    pub fn is_known(&self) -> bool {
+        !self.entries.is_empty()
+    }
+}
+
+/// Split Japanese text into words by longest match against the dictionary, trying the
+/// dictionary forms of inflected words at each position
+pub fn segment(text: &str, dictionary: &OfflineDictionary) -> Vec<Token> {
+    let chars: Vec<char> = text.chars().collect();
+    // Inflections can make a word several characters longer than its dictionary form
+    let max_len = dictionary.longest_key() + 8;
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut start = 0;
+    // Whether the last token is a run of unknown characters that the next one may join
+    let mut unknown_run = false;
+
+    while start < chars.len() {
+        if is_separator(chars[start]) {
+            unknown_run = false;
+            start += 1;
+            continue;
+        }
+
+        let longest = max_len.min(chars.len() - start);
+        let matched = (1..=longest).rev().find_map(|len| {
+            let surface: String = chars[start..start + len].iter().collect();
+            match_word(&surface, dictionary).map(|token| (len, token))
+        });
+
+        match matched {
+            Some((len, token)) => {
+                tokens.push(token);
+                unknown_run = false;
+                start += len;
+            }
+            None => {
+                // Unknown characters are grouped together so they show up as one gap
+                match tokens.last_mut() {
+                    Some(last) if unknown_run => last.surface.push(chars[start]),
+                    _ => tokens.push(Token {
+                        surface: chars[start].to_string(),
+                        base: None,
+                        reasons: Vec::new(),
+                        entries: Vec::new(),
+                    }),
+                }
+                unknown_run = true;
+                start += 1;
+            }
+        }
+    }
+
+    tokens
+}
+
+fn match_word(surface: &str, dictionary: &OfflineDictionary) -> Option<Token> {
+    if surface.chars().any(is_separator) {
+        return None;
+    }
+
+    deinflect(surface).into_iter().find_map(|deinflection| {
+        let entries = dictionary.lookup_deinflected(&deinflection);
+        if entries.is_empty() {
+            return None;
+        }
+        Some(Token {
+            surface: surface.to_string(),
+            base: Some(deinflection.word).filter(|word| word != surface),
+            reasons: deinflection.reasons,
+            entries,
+        })
+    })
+}
+
+fn is_separator(ch: char) -> bool {
+    ch.is_whitespace() || ch.is_ascii_punctuation() || "。、！？「」『』（）・…―〜：；，．【】".contains(ch)
+}
+
+/// Glossary of the words in a sentence, in sentence order
+pub fn format_glossary(tokens: &[Token], include_particles: bool, renderer: &dyn Renderer, options: &FormatOptions) -> String {
+    let tokens: Vec<&Token> = tokens.iter()
+        .filter(|token| include_particles || !token.is_particle())
+        .collect();
+
+    let titles: Vec<String> = tokens.iter()
+        .map(|token| {
+            let mut title = token.surface.clone();
+            if let Some(base) = &token.base {
+                title.push_str(&format!(" → {}", base));
+            }
+            if !token.reasons.is_empty() {
+                title.push_str(&format!(" ({})", token.reasons.join(" < ")));
+            }
+            title
+        })
+        .collect();
+
+    let sections: Vec<Section> = tokens.iter()
+        .zip(&titles)
+        .map(|(token, title)| Section { title, entries: &token.entries })
+        .collect();
+    renderer.render_sections(&sections, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+私(P) [わたし(P)] /(pn) I/me/(P)/
+は [は] /(prt) topic marker particle/
+学校(P) [がっこう(P)] /(n) school/(P)/
+行く(P) [いく(P)] /(v5k-s,vi) to go/(P)/
+食べる(P) [たべる(P)] /(v1,vt) to eat/(P)/
+";
+
+    fn surfaces(tokens: &[Token]) -> Vec<&str> {
+        tokens.iter().map(|token| token.surface.as_str()).collect()
+    }
+
+    #[test]
+    fn segments_mixed_kana_and_kanji() {
+        let dictionary = OfflineDictionary::parse(SAMPLE);
+        let tokens = segment("私は学校へ行って、パンを食べなかった。", &dictionary);
+        assert_eq!(surfaces(&tokens), ["私", "は", "学校", "へ", "行って", "パンを", "食べなかった"]);
+
+        assert_eq!(tokens[4].base.as_deref(), Some("行く"));
+        assert_eq!(tokens[4].reasons, ["te-form"]);
+        assert_eq!(tokens[6].base.as_deref(), Some("食べる"));
+        assert_eq!(tokens[6].reasons, ["past", "negative"]);
+    }
+
+    #[test]
+    fn ends_unknown_runs_at_punctuation() {
+        let dictionary = OfflineDictionary::parse(SAMPLE);
+        let tokens = segment("パン、ケーキ。 ジュース", &dictionary);
+        assert_eq!(surfaces(&tokens), ["パン", "ケーキ", "ジュース"]);
+        assert!(tokens.iter().all(|token| !token.is_known()));
+    }
+
+    #[test]
+    fn groups_unknown_characters() {
+        let dictionary = OfflineDictionary::parse(SAMPLE);
+        let tokens = segment("私は学校へ行って、パンを食べなかった。", &dictionary);
+        assert!(!tokens[3].is_known());
+        assert!(!tokens[5].is_known());
+        assert!(tokens[1].is_particle());
+        assert!(tokens.iter().filter(|token| token.is_known()).all(|token| !token.entries.is_empty()));
+    }
+}
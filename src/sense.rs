@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+
+/// One sense of a definition, with its own sub-senses (㋐㋑, アイ) and usage examples
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Sense {
+    /// Marker the dictionary gives the sense, e.g. "１" or "㋐"; empty if it has none
+    pub label: String,
+    /// Part of speech where it changes from the senses before, e.g. a suru verb after a noun
+    pub part_of_speech: String,
+    pub text: String,
+    /// Example phrases like 「―な判断」, brackets included
+    pub examples: Vec<String>,
+    pub subsenses: Vec<Sense>,
+}
+
+impl Sense {
+    pub fn new(label: &str) -> Self {
+        Self { label: label.to_string(), ..Self::default() }
+    }
+
+    /// The sense as a single line of text, examples and sub-senses included
+    pub fn plain(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.part_of_speech.is_empty() {
+            parts.push(format!("({})", self.part_of_speech));
+        }
+        if !self.text.is_empty() || !self.examples.is_empty() {
+            parts.push(format!("{}{}", self.text, self.examples.concat()));
+        }
+        for subsense in &self.subsenses {
+            parts.push(format!("{}{}", subsense.label, subsense.plain()));
+        }
+        parts.join(" ")
+    }
+
+    /// Move trailing examples out of `text` and tidy up the sub-senses the same way
+    pub fn finish(&mut self) {
+        let (text, examples) = split_examples(&self.text);
+        self.text = text;
+        self.examples = examples;
+        for subsense in &mut self.subsenses {
+            subsense.finish();
+        }
+    }
+}
+
+/// A full example sentence and its translation, as given by bilingual dictionaries
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExampleSentence {
+    pub text: String,
+    pub translation: String,
+}
+
+/// Split a definition like `物事の正しいこと。「―な判断」「―を期す」` into its text and the
+/// 「…」 examples that follow the last sentence. Quotes inside a sentence are left alone.
+pub fn split_examples(text: &str) -> (String, Vec<String>) {
+    let text = text.trim();
+    let mut start = text.len();
+    let mut examples = Vec::new();
+
+    // Walk back over 「…」 groups at the end of the text
+    let mut rest = text;
+    while let Some(stripped) = rest.trim_end().strip_suffix('」') {
+        let Some(open) = matching_open(stripped) else {
+            break;
+        };
+        examples.push(rest.trim_end()[open..].to_string());
+        start = open;
+        rest = &stripped[..open];
+    }
+
+    let definition = text[..start].trim_end();
+    // Quotes are only examples when they come after a full sentence
+    if examples.is_empty() || !(definition.ends_with('。') || definition.is_empty()) {
+        return (text.to_string(), Vec::new());
+    }
+    examples.reverse();
+    (definition.to_string(), examples)
+}
+
+/// Split running text like `［名］(スル)１ 正しく解釈すること。２ 正しい答え。` at its full-width
+/// sense numbers, giving the text before the first one and the numbered senses with examples split out.
+/// A number only starts a sense at the start of the text or after the end of a sentence or bracket.
+pub fn split_numbered(text: &str) -> (String, Vec<Sense>) {
+    const DIGITS: &str = "１２３４５６７８９０";
+    let mut preamble = String::new();
+    let mut senses: Vec<Sense> = Vec::new();
+    let mut previous = None;
+    let mut chars = text.trim().chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        let starts_sense = DIGITS.contains(ch)
+            && previous.is_none_or(|previous: char| "。」』）)］〕〙》 　".contains(previous));
+        if starts_sense {
+            let mut label = ch.to_string();
+            while let Some(&next) = chars.peek().filter(|next| DIGITS.contains(**next)) {
+                label.push(next);
+                chars.next();
+            }
+            senses.push(Sense::new(&label));
+        } else {
+            match senses.last_mut() {
+                Some(sense) => sense.text.push(ch),
+                None => preamble.push(ch),
+            }
+        }
+        previous = Some(ch);
+    }
+
+    senses.iter_mut().for_each(Sense::finish);
+    (preamble.trim().to_string(), senses)
+}
+
+/// Leading ［名］ / 〘名〙 markers and (スル) style notes, and the text after them
+pub fn split_part_of_speech(text: &str) -> (String, String) {
+    let mut rest = text.trim();
+    let mut part_of_speech = String::new();
+
+    loop {
+        let close = match rest.chars().next() {
+            Some('［') => '］',
+            Some('〘') => '〙',
+            Some('(') => ')',
+            _ => break,
+        };
+        let Some(end) = rest.find(close) else {
+            break;
+        };
+        let end = end + close.len_utf8();
+        part_of_speech.push_str(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+
+    (part_of_speech, rest.to_string())
+}
+
+/// Part of speech and senses of running text like `［名］(スル)《「正しく解く」の意》１ …２ …`.
+/// Text between the part of speech and the first number is kept as an unnumbered leading sense.
+pub fn split_description(text: &str) -> (String, Vec<Sense>) {
+    let (preamble, mut senses) = split_numbered(text);
+    let (part_of_speech, text) = split_part_of_speech(&preamble);
+    if !text.is_empty() || senses.is_empty() {
+        let mut sense = Sense { text, ..Sense::default() };
+        sense.finish();
+        senses.insert(0, sense);
+    }
+    (part_of_speech, senses)
+}
+
+/// Byte offset of the 「 that opens a quote whose closing 」 has been stripped from the end of `text`
+fn matching_open(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (offset, ch) in text.char_indices().rev() {
+        match ch {
+            '」' => depth += 1,
+            '「' if depth == 0 => return Some(offset),
+            '「' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_trailing_examples() {
+        let (text, examples) = split_examples("物事の正しいこと。「―な判断」「―を期す」");
+        assert_eq!(text, "物事の正しいこと。");
+        assert_eq!(examples, ["「―な判断」", "「―を期す」"]);
+    }
+
+    #[test]
+    fn keeps_nested_quotes_in_one_example() {
+        let (text, examples) = split_examples("言う。「『はい』と―・う」");
+        assert_eq!(text, "言う。");
+        assert_eq!(examples, ["「『はい』と―・う」"]);
+    }
+
+    #[test]
+    fn leaves_quotes_inside_a_definition() {
+        let text = "「思ひ」の転で、「考える」の意。";
+        assert_eq!(split_examples(text), (text.to_string(), Vec::new()));
+
+        // Quotes ending the text are only examples after a full sentence
+        let text = "いわゆる「正解」";
+        assert_eq!(split_examples(text), (text.to_string(), Vec::new()));
+    }
+
+    #[test]
+    fn treats_text_of_only_quotes_as_examples() {
+        assert_eq!(split_examples("「―な判断」"), (String::new(), vec!["「―な判断」".to_string()]));
+    }
+
+    #[test]
+    fn splits_numbered_senses() {
+        let (preamble, senses) = split_numbered("［名］(スル)１ 正しく解釈すること。「―を出す」２ 正しい答え。");
+        assert_eq!(preamble, "［名］(スル)");
+        assert_eq!(senses.len(), 2);
+        assert_eq!(senses[0].label, "１");
+        assert_eq!(senses[0].text, "正しく解釈すること。");
+        assert_eq!(senses[0].examples, ["「―を出す」"]);
+        assert_eq!(senses[1].text, "正しい答え。");
+    }
+
+    #[test]
+    fn keeps_text_before_the_first_number_as_a_sense() {
+        let (part_of_speech, senses) = split_description("［名］(スル)《「正しく解く」の意》１ 正しく解釈すること。２ 正しい答え。");
+        assert_eq!(part_of_speech, "［名］(スル)");
+        let labels: Vec<&str> = senses.iter().map(|sense| sense.label.as_str()).collect();
+        assert_eq!(labels, ["", "１", "２"]);
+        assert_eq!(senses[0].text, "《「正しく解く」の意》");
+
+        let (part_of_speech, senses) = split_description("〘名〙１ 正しい答え。２ 正しい解釈。");
+        assert_eq!(part_of_speech, "〘名〙");
+        assert_eq!(senses.len(), 2);
+    }
+
+    #[test]
+    fn ignores_numbers_inside_a_sentence() {
+        let (preamble, senses) = split_numbered("第２版で改めた語。");
+        assert_eq!(preamble, "第２版で改めた語。");
+        assert!(senses.is_empty());
+    }
+
+    #[test]
+    fn splits_leading_part_of_speech() {
+        assert_eq!(
+            split_part_of_speech("［名］(スル) 正しく解釈すること。"),
+            ("［名］(スル)".to_string(), "正しく解釈すること。".to_string()),
+        );
+        assert_eq!(split_part_of_speech("〘名〙答え。"), ("〘名〙".to_string(), "答え。".to_string()));
+        assert_eq!(split_part_of_speech("答え。"), (String::new(), "答え。".to_string()));
+    }
+
+    #[test]
+    fn flattens_sub_senses_into_plain_text() {
+        let mut sense = Sense::new("１");
+        sense.text = "答え。「―だ」".to_string();
+        let mut subsense = Sense::new("ア");
+        subsense.text = "正しい答え。「―を出す」".to_string();
+        sense.subsenses.push(subsense);
+        sense.finish();
+
+        assert_eq!(sense.examples, ["「―だ」"]);
+        assert_eq!(sense.subsenses[0].examples, ["「―を出す」"]);
+        assert_eq!(sense.plain(), "答え。「―だ」 ア正しい答え。「―を出す」");
+    }
+}
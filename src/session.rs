@@ -0,0 +1,359 @@
+use crate::{Dictionary, DictionaryEntry, DictionaryResult};
+use crate::history::History;
+use crate::pitch_accent::AccentDictionary;
+use crate::reference::{Reference, referenced_entries};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Lookup state shared across searches: the accent dictionary, history recording
+/// and an in-memory cache of results
+#[derive(Default)]
+pub struct Session {
+    accents: Option<AccentDictionary>,
+    history_path: Option<PathBuf>,
+    /// Opened on the first lookup to record, then kept so the file is only read once
+    history: Option<History>,
+    /// Results by the backend that answered and the term
+    cache: HashMap<(String, String), (Instant, Vec<DictionaryEntry>)>,
+    cache_ttl: Option<Duration>,
+    limit: Option<usize>,
+    dictionaries: Vec<String>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_accents(mut self, accents: AccentDictionary) -> Self {
+        self.accents = Some(accents);
+        self
+    }
+
+    /// Record every successful lookup in the history file at `path`
+    pub fn with_history(mut self, path: PathBuf) -> Self {
+        self.history_path = Some(path);
+        self
+    }
+
+    /// Refetch cached results once they are older than `ttl`
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = Some(ttl);
+        self
+    }
+
+    /// Return at most `limit` entries per lookup
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Only return entries from these sub-dictionaries (see `DictionaryEntry::in_dictionaries`)
+    pub fn with_dictionaries(mut self, dictionaries: Vec<String>) -> Self {
+        self.dictionaries = dictionaries;
+        self
+    }
+
+    pub fn lookup(&mut self, dictionary: &dyn Dictionary, term: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
+        let entries = self.lookup_unlimited(dictionary, term)?;
+        Ok(self.shown(entries))
+    }
+
+    fn lookup_unlimited(&mut self, dictionary: &dyn Dictionary, term: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
+        if let Some(entries) = self.cached(dictionary, term) {
+            return Ok(entries.clone());
+        }
+
+        let entries = dictionary.lookup(term)?;
+        Ok(self.store(dictionary, term, entries))
+    }
+
+    /// The dictionary's own cached result, else the first backend with cached entries as
+    /// long as every backend before it is cached as having found nothing
+    fn cached(&self, dictionary: &dyn Dictionary, term: &str) -> Option<&Vec<DictionaryEntry>> {
+        let backends = dictionary.backends();
+        if let Some(entries) = self.cached_from(&cache_key(&backends), term) {
+            return Some(entries);
+        }
+
+        let mut empty = None;
+        for backend in backends {
+            let entries = self.cached_from(backend, term)?;
+            if !entries.is_empty() {
+                return Some(entries);
+            }
+            empty = Some(entries);
+        }
+        empty
+    }
+
+    fn cached_from(&self, backend: &str, term: &str) -> Option<&Vec<DictionaryEntry>> {
+        let (fetched, entries) = self.cache.get(&(backend.to_string(), term.to_string()))?;
+        match self.cache_ttl {
+            Some(ttl) if fetched.elapsed() > ttl => None,
+            _ => Some(entries),
+        }
+    }
+
+    /// Entries left after the dictionary filter and result limit
+    fn shown(&self, mut entries: Vec<DictionaryEntry>) -> Vec<DictionaryEntry> {
+        if !self.dictionaries.is_empty() {
+            entries.retain(|entry| entry.in_dictionaries(&self.dictionaries));
+        }
+        if let Some(limit) = self.limit {
+            entries.truncate(limit);
+        }
+        entries
+    }
+
+    /// Look up several terms with at most `jobs` requests in flight. Results are in the
+    /// order of `terms`; cached and repeated terms are only fetched once.
+    pub fn lookup_many(
+        &mut self,
+        dictionary: &dyn Dictionary,
+        terms: &[String],
+        jobs: usize,
+    ) -> Vec<DictionaryResult<Vec<DictionaryEntry>>> {
+        let mut missing: Vec<&str> = Vec::new();
+        for term in terms {
+            if self.cached(dictionary, term).is_none() && !missing.contains(&term.as_str()) {
+                missing.push(term);
+            }
+        }
+
+        // Errors are turned into strings since they have to cross threads
+        let fetched = Mutex::new(Vec::new());
+        let next = AtomicUsize::new(0);
+        thread::scope(|scope| {
+            for _ in 0..jobs.clamp(1, missing.len().max(1)) {
+                scope.spawn(|| {
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(term) = missing.get(i) else {
+                            break;
+                        };
+                        let result = dictionary.lookup(term).map_err(|e| e.to_string());
+                        fetched.lock().unwrap().push((i, result));
+                    }
+                });
+            }
+        });
+
+        let mut errors = HashMap::new();
+        for (i, result) in fetched.into_inner().unwrap() {
+            match result {
+                Ok(entries) => {
+                    self.store(dictionary, missing[i], entries);
+                }
+                Err(e) => {
+                    errors.insert(missing[i].to_string(), e);
+                }
+            }
+        }
+
+        terms.iter()
+            .map(|term| match errors.get(term) {
+                Some(e) => Err(e.clone().into()),
+                None => Ok(self.shown(self.cached(dictionary, term).cloned().unwrap_or_default())),
+            })
+            .collect()
+    }
+
+    /// Annotate, record and cache freshly fetched entries, under the dictionary and under
+    /// the backend that answered
+    fn store(&mut self, dictionary: &dyn Dictionary, term: &str, mut entries: Vec<DictionaryEntry>) -> Vec<DictionaryEntry> {
+        if let Some(accents) = &self.accents {
+            accents.annotate(&mut entries);
+        }
+
+        // A chain's miss may hide backends that failed, so it is only cached for the chain itself
+        let backends = dictionary.backends();
+        let now = Instant::now();
+        self.cache.insert((cache_key(&backends), term.to_string()), (now, entries.clone()));
+        if entries.is_empty() {
+            return entries;
+        }
+
+        let backend = entries.iter()
+            .map(|entry| entry.source.as_str())
+            .find(|source| backends.contains(source))
+            .unwrap_or(dictionary.name())
+            .to_string();
+        self.record_history(term, &backend, &entries);
+        self.cache.insert((backend, term.to_string()), (now, entries.clone()));
+        entries
+    }
+
+    /// Look up the target of a reference, narrowed down to the referenced word
+    pub fn follow(&mut self, dictionary: &dyn Dictionary, reference: &Reference) -> DictionaryResult<Vec<DictionaryEntry>> {
+        // The referenced word may be past the result limit before narrowing
+        let entries = self.lookup_unlimited(dictionary, &reference.term)?;
+        Ok(self.shown(referenced_entries(entries, reference)))
+    }
+
+    fn record_history(&mut self, term: &str, backend: &str, entries: &[DictionaryEntry]) {
+        let Some(path) = &self.history_path else {
+            return;
+        };
+
+        // History is a convenience, a broken history file shouldn't stop lookups
+        if self.history.is_none() {
+            match History::open(path) {
+                Ok(history) => self.history = Some(history),
+                Err(e) => {
+                    eprintln!("Warning: could not record history: {}", e);
+                    return;
+                }
+            }
+        }
+        if let Some(history) = &mut self.history
+            && let Err(e) = history.add(term, backend, entries)
+        {
+            eprintln!("Warning: could not record history: {}", e);
+        }
+    }
+}
+
+/// Cache key for a dictionary's own results: its backend, or a chain's backends joined by commas
+fn cache_key(backends: &[&str]) -> String {
+    backends.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fallback::Fallback;
+    use std::sync::Arc;
+
+    /// Finds `found` terms (or fails every lookup) and counts how often it is asked
+    struct Stub {
+        name: &'static str,
+        found: &'static [&'static str],
+        fails: bool,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl Dictionary for Stub {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn lookup(&self, term: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            if self.fails {
+                return Err("timed out".into());
+            }
+            if !self.found.contains(&term) {
+                return Ok(Vec::new());
+            }
+            let entry = DictionaryEntry::new(term.to_string(), String::new(), vec![self.name.to_string()]);
+            Ok(vec![entry.with_source(self.name)])
+        }
+    }
+
+    fn stub(name: &'static str, found: &'static [&'static str]) -> (Stub, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        (Stub { name, found, fails: false, calls: calls.clone() }, calls)
+    }
+
+    fn failing(name: &'static str) -> (Stub, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        (Stub { name, found: &[], fails: true, calls: calls.clone() }, calls)
+    }
+
+    #[test]
+    fn caches_fallback_results_under_the_backend_that_answered() {
+        let (first, first_calls) = stub("first", &[]);
+        let (second, second_calls) = stub("second", &["正解"]);
+        let chain = Fallback::new(vec![Box::new(first), Box::new(second)]);
+        let mut session = Session::new();
+
+        let entries = session.lookup(&chain, "正解").unwrap();
+        assert_eq!(entries[0].source, "second");
+        assert!(session.cache.contains_key(&("second".to_string(), "正解".to_string())));
+        assert!(!session.cache.contains_key(&("first".to_string(), "正解".to_string())));
+
+        // The chain is answered from the cache, but the first backend on its own still asks
+        session.lookup(&chain, "正解").unwrap();
+        assert_eq!(second_calls.load(Ordering::Relaxed), 1);
+        let (first, _) = stub("first", &[]);
+        assert!(session.lookup(&first, "正解").unwrap().is_empty());
+        assert_eq!(first_calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn caches_a_miss_for_the_chain_only() {
+        let (first, first_calls) = stub("first", &[]);
+        let (second, second_calls) = stub("second", &[]);
+        let chain = Fallback::new(vec![Box::new(first), Box::new(second)]);
+        let mut session = Session::new();
+
+        assert!(session.lookup(&chain, "無い").unwrap().is_empty());
+        assert!(session.lookup(&chain, "無い").unwrap().is_empty());
+        assert_eq!(first_calls.load(Ordering::Relaxed), 1);
+        assert_eq!(second_calls.load(Ordering::Relaxed), 1);
+
+        let (first, first_calls) = stub("first", &[]);
+        assert!(session.lookup(&first, "無い").unwrap().is_empty());
+        assert_eq!(first_calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn does_not_cache_a_failed_backend_as_a_miss() {
+        let (first, _) = failing("first");
+        let (second, _) = stub("second", &[]);
+        let chain = Fallback::new(vec![Box::new(first), Box::new(second)]);
+        let mut session = Session::new();
+
+        assert!(session.lookup(&chain, "正解").unwrap().is_empty());
+        let (first, first_calls) = stub("first", &["正解"]);
+        assert_eq!(session.lookup(&first, "正解").unwrap().len(), 1);
+        assert_eq!(first_calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn keeps_the_chain_order_with_a_cached_later_backend() {
+        let (second, _) = stub("second", &["思う"]);
+        let mut session = Session::new();
+        session.lookup(&second, "思う").unwrap();
+
+        let (first, first_calls) = stub("first", &["思う"]);
+        let (second, second_calls) = stub("second", &["思う"]);
+        let chain = Fallback::new(vec![Box::new(first), Box::new(second)]);
+        let entries = session.lookup(&chain, "思う").unwrap();
+        assert_eq!(entries[0].source, "first");
+        assert_eq!(first_calls.load(Ordering::Relaxed), 1);
+        assert_eq!(second_calls.load(Ordering::Relaxed), 0);
+
+        // Once the first backend is cached as empty, the later one's cache answers
+        let (first, _) = stub("first", &[]);
+        let mut session = Session::new();
+        session.lookup(&first, "思う").unwrap();
+        session.lookup(&stub("second", &["思う"]).0, "思う").unwrap();
+        let (first, first_calls) = stub("first", &[]);
+        let (second, second_calls) = stub("second", &["思う"]);
+        let chain = Fallback::new(vec![Box::new(first), Box::new(second)]);
+        assert_eq!(session.lookup(&chain, "思う").unwrap()[0].source, "second");
+        assert_eq!(first_calls.load(Ordering::Relaxed) + second_calls.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn records_the_backend_that_answered_in_the_history() {
+        let path = std::env::temp_dir().join(format!("jisho-session-test-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let (first, _) = stub("first", &[]);
+        let (second, _) = stub("second", &["正解"]);
+        let chain = Fallback::new(vec![Box::new(first), Box::new(second)]);
+        let mut session = Session::new().with_history(path.clone());
+
+        session.lookup(&chain, "正解").unwrap();
+        let history = History::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(history.records().len(), 1);
+        assert_eq!(history.records()[0].backend, "second");
+    }
+}
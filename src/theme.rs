@@ -0,0 +1,235 @@
+use crate::DictionaryResult;
+use colored::{Color, ColoredString, Colorize};
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Color and attributes for one element of the output, e.g. `bright_cyan bold`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    /// None keeps the terminal's own foreground color
+    pub color: Option<Color>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub dimmed: bool,
+}
+
+impl Style {
+    pub const fn color(color: Color) -> Self {
+        Self { color: Some(color), bold: false, italic: false, underline: false, dimmed: false }
+    }
+
+    pub const fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    pub fn paint(&self, text: &str) -> ColoredString {
+        let mut painted = match self.color {
+            Some(color) => text.color(color),
+            None => text.normal(),
+        };
+        if self.bold {
+            painted = painted.bold();
+        }
+        if self.italic {
+            painted = painted.italic();
+        }
+        if self.underline {
+            painted = painted.underline();
+        }
+        if self.dimmed {
+            painted = painted.dimmed();
+        }
+        painted
+    }
+}
+
+impl FromStr for Style {
+    type Err = String;
+
+    /// Space separated color name (`cyan`, `bright_cyan`, `#5f87af` or `default`) and
+    /// attributes (`bold`, `italic`, `underline`, `dim`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut style = Style::default();
+        for word in s.split_whitespace() {
+            match word {
+                "bold" => style.bold = true,
+                "italic" => style.italic = true,
+                "underline" => style.underline = true,
+                "dim" | "dimmed" => style.dimmed = true,
+                "default" | "none" => style.color = None,
+                hex if hex.starts_with('#') => style.color = Some(parse_hex(hex)?),
+                name => {
+                    let color = name.replace('_', " ").parse::<Color>()
+                        .map_err(|_| format!("unknown color or attribute: {}", name))?;
+                    style.color = Some(color);
+                }
+            }
+        }
+        Ok(style)
+    }
+}
+
+fn parse_hex(hex: &str) -> Result<Color, String> {
+    let digits = &hex[1..];
+    let channel = |i: usize| digits.get(i..i + 2).and_then(|channel| u8::from_str_radix(channel, 16).ok());
+    match (digits.len(), channel(0), channel(2), channel(4)) {
+        (6, Some(r), Some(g), Some(b)) => Ok(Color::TrueColor { r, g, b }),
+        _ => Err(format!("invalid hex color: {} (expected #rrggbb)", hex)),
+    }
+}
+
+/// Styles for each element of the text output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub headword: Style,
+    pub accent: Style,
+    pub pos: Style,
+    pub sense_number: Style,
+    pub gloss: Style,
+    /// Usage examples like 「―な判断」 under a sense
+    pub example: Style,
+    /// Marker in front of a followed reference's sense
+    pub highlight: Style,
+    /// 類語: and 参照: labels
+    pub label: Style,
+    pub reference_number: Style,
+    /// Section headers like `── 読む ──` and notices
+    pub heading: Style,
+    /// Secondary text, e.g. reference kinds and paging hints
+    pub muted: Style,
+    pub warning: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    /// Bright colors for dark backgrounds
+    pub fn dark() -> Self {
+        Self {
+            headword: Style::color(Color::BrightCyan),
+            accent: Style::color(Color::BrightBlue),
+            pos: Style::color(Color::BrightYellow),
+            sense_number: Style::color(Color::BrightMagenta),
+            gloss: Style::color(Color::BrightWhite),
+            example: Style::color(Color::White),
+            highlight: Style::color(Color::BrightGreen),
+            label: Style::color(Color::BrightGreen),
+            reference_number: Style::color(Color::BrightMagenta),
+            heading: Style::color(Color::BrightGreen),
+            muted: Style::color(Color::BrightBlack),
+            warning: Style::color(Color::BrightYellow),
+        }
+    }
+
+    /// Darker colors for light backgrounds, glosses in the terminal's own foreground color
+    pub fn light() -> Self {
+        Self {
+            headword: Style::color(Color::Blue).bold(),
+            accent: Style::color(Color::Cyan),
+            pos: Style::color(Color::Yellow),
+            sense_number: Style::color(Color::Magenta),
+            gloss: Style::default(),
+            example: Style::color(Color::BrightBlack),
+            highlight: Style::color(Color::Green),
+            label: Style::color(Color::Green),
+            reference_number: Style::color(Color::Magenta),
+            heading: Style::color(Color::Green).bold(),
+            muted: Style::color(Color::BrightBlack),
+            warning: Style::color(Color::Red),
+        }
+    }
+
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            _ => None,
+        }
+    }
+
+    /// A built-in theme name or the path of a theme file
+    pub fn load_named(name: &str) -> DictionaryResult<Self> {
+        match Self::by_name(name) {
+            Some(theme) => Ok(theme),
+            None if Path::new(name).exists() => Self::load(Path::new(name)),
+            None => Err(format!("unknown theme: {} (expected dark, light or a theme file)", name).into()),
+        }
+    }
+
+    pub fn load(path: &Path) -> DictionaryResult<Self> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("could not read theme {}: {}", path.display(), e))?;
+        Ok(Self::parse(&contents)?)
+    }
+
+    /// `element = style` lines. `base = light` picks the theme to start from (default: dark).
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut theme = Self::dark();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (element, value) = line.split_once('=')
+                .ok_or_else(|| format!("line {}: expected element = style", number + 1))?;
+            let (element, value) = (element.trim(), value.trim());
+            if element == "base" {
+                theme = Self::by_name(value).ok_or_else(|| format!("line {}: unknown theme: {}", number + 1, value))?;
+                continue;
+            }
+
+            let style = value.parse().map_err(|e| format!("line {}: {}", number + 1, e))?;
+            *theme.element_mut(element).ok_or_else(|| format!("line {}: unknown element: {}", number + 1, element))? = style;
+        }
+        Ok(theme)
+    }
+
+    fn element_mut(&mut self, element: &str) -> Option<&mut Style> {
+        match element {
+            "headword" => Some(&mut self.headword),
+            "accent" => Some(&mut self.accent),
+            "pos" => Some(&mut self.pos),
+            "sense_number" => Some(&mut self.sense_number),
+            "gloss" => Some(&mut self.gloss),
+            "example" => Some(&mut self.example),
+            "highlight" => Some(&mut self.highlight),
+            "label" => Some(&mut self.label),
+            "reference_number" => Some(&mut self.reference_number),
+            "heading" => Some(&mut self.heading),
+            "muted" => Some(&mut self.muted),
+            "warning" => Some(&mut self.warning),
+            _ => None,
+        }
+    }
+}
+
+/// Use `theme` for all text output. Only the first call has an effect.
+pub fn set(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+/// The theme set with `set`, or the dark theme
+pub fn current() -> &'static Theme {
+    THEME.get_or_init(Theme::dark)
+}
+
+/// Turn color off when `NO_COLOR` is set or stdout is not a terminal, unless
+/// `CLICOLOR_FORCE` asks for it
+pub fn configure_color() {
+    let forced = env::var_os("CLICOLOR_FORCE").is_some_and(|value| !value.is_empty() && value != "0");
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    colored::control::set_override(forced || (!no_color && io::stdout().is_terminal()));
+}
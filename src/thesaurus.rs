@@ -0,0 +1,168 @@
+use crate::{Dictionary, DictionaryEntry, DictionaryResult};
+use crate::http;
+use crate::reference::{Reference, ReferenceKind};
+use crate::theme;
+use crate::wrap;
+use scraper::{ElementRef, Html, Selector};
+
+pub const SOURCE: &str = "thesaurus";
+
+/// Synonyms sharing one nuance (意義素) on a Weblio 類語辞典 page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SynonymGroup {
+    /// What the synonyms have in common, e.g. 正しい答え
+    pub nuance: String,
+    pub synonyms: Vec<String>,
+}
+
+/// Weblio's thesaurus as a backend: one entry per synonym group, the nuance as its definition
+pub struct Thesaurus;
+
+impl Dictionary for Thesaurus {
+    fn name(&self) -> &'static str {
+        SOURCE
+    }
+
+    fn lookup(&self, term: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
+        Ok(group_entries(term, &lookup_thesaurus(term)?))
+    }
+}
+
+pub fn lookup_thesaurus(term: &str) -> DictionaryResult<Vec<SynonymGroup>> {
+    let url = format!("https://thesaurus.weblio.jp/content/{}", term);
+    let Some(html) = http::get_page(SOURCE, &url)? else {
+        return Ok(Vec::new());
+    };
+    let document = Html::parse_document(&html);
+    Ok(parse_groups(&document))
+}
+
+/// Entries for `Dictionary` lookups, so synonyms can be followed like any other reference
+pub fn group_entries(term: &str, groups: &[SynonymGroup]) -> Vec<DictionaryEntry> {
+    groups.iter()
+        .map(|group| {
+            let references = group.synonyms.iter()
+                .map(|synonym| Reference::new(ReferenceKind::Synonym, synonym.clone()))
+                .collect();
+            DictionaryEntry::new(term.to_string(), String::new(), vec![group.nuance.clone()])
+                .with_synonyms(group.synonyms.clone())
+                .with_references(references)
+                .with_source(SOURCE)
+                .with_dictionary("類語辞典")
+        })
+        .collect()
+}
+
+/// Groups as `nuance: synonym, synonym, ...` lines under the term
+pub fn format_groups(term: &str, groups: &[SynonymGroup], width: Option<usize>) -> String {
+    if groups.is_empty() {
+        return "No synonyms found.".to_string();
+    }
+
+    let theme = theme::current();
+    let mut lines = vec![theme.headword.paint(term).to_string()];
+    for group in groups {
+        let label = format!("  {}: ", group.nuance);
+        let synonyms = group.synonyms.join(", ");
+        let indent = " ".repeat(wrap::width(&label));
+        for (i, line) in wrap::wrap_hanging(&label, &synonyms, width).iter().enumerate() {
+            if i == 0 {
+                lines.push(format!("  {} {}", theme.label.paint(&format!("{}:", group.nuance)), theme.gloss.paint(line)));
+            } else {
+                lines.push(format!("{}{}", indent, theme.gloss.paint(line)));
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/// Rows of the 意義素 / 類語・言い換え・同義語 tables: the nuance in the first cell, linked synonyms in the second.
+/// Only tables with a 意義素 header count, so rankings and other tables on the page are skipped.
+fn parse_groups(document: &Html) -> Vec<SynonymGroup> {
+    let table_selector = Selector::parse("table").unwrap();
+    let row_selector = Selector::parse("tr").unwrap();
+    let cell_selector = Selector::parse("th, td").unwrap();
+    let mut groups: Vec<SynonymGroup> = Vec::new();
+
+    let is_header = |cell: &ElementRef| cell.text().collect::<String>().trim() == "意義素";
+    let tables = document.select(&table_selector)
+        .filter(|table| table.select(&cell_selector).any(|cell| is_header(&cell)));
+
+    for row in tables.flat_map(|table| table.select(&row_selector)) {
+        let cells: Vec<ElementRef> = row.select(&cell_selector).collect();
+        let [nuance_cell, synonyms_cell, ..] = cells.as_slice() else {
+            continue;
+        };
+
+        let nuance = nuance_cell.text().collect::<String>().trim().to_string();
+        // Header rows label the columns instead of holding a group
+        if nuance.is_empty() || is_header(nuance_cell) {
+            continue;
+        }
+
+        let synonyms = cell_synonyms(synonyms_cell);
+        if synonyms.is_empty() {
+            continue;
+        }
+        // The same nuance can be split over several rows
+        match groups.iter_mut().find(|group| group.nuance == nuance) {
+            Some(group) => {
+                for synonym in synonyms {
+                    if !group.synonyms.contains(&synonym) {
+                        group.synonyms.push(synonym);
+                    }
+                }
+            }
+            None => groups.push(SynonymGroup { nuance, synonyms }),
+        }
+    }
+
+    groups
+}
+
+/// Linked words in a cell, or its text split on ・ and 、 when nothing is linked
+fn cell_synonyms(cell: &ElementRef) -> Vec<String> {
+    let link_selector = Selector::parse("a").unwrap();
+    let mut synonyms: Vec<String> = cell.select(&link_selector)
+        .map(|link| link.text().collect::<String>().trim().to_string())
+        .filter(|synonym| !synonym.is_empty())
+        .collect();
+
+    if synonyms.is_empty() {
+        synonyms = cell.text().collect::<String>()
+            .split(['・', '、', '\n'])
+            .map(|synonym| synonym.trim().to_string())
+            .filter(|synonym| !synonym.is_empty())
+            .collect();
+    }
+    synonyms.dedup();
+    synonyms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_only_tables_with_a_nuance_header() {
+        let document = Html::parse_document(
+            "<table><tr><td>1位</td><td><a>正解</a></td></tr></table>\
+            <table class=\"wtbl\">\
+            <tr><th>意義素</th><th>類語・言い換え・同義語</th></tr>\
+            <tr><td>正しい答え</td><td><a>正答</a>・<a>正しい答え</a></td></tr>\
+            <tr><td>正しい答え</td><td><a>正答</a>・<a>答え</a></td></tr>\
+            <tr><td>正しい解釈</td><td>適切な解釈・正しい理解</td></tr>\
+            </table>",
+        );
+        assert_eq!(parse_groups(&document), [
+            SynonymGroup {
+                nuance: "正しい答え".to_string(),
+                synonyms: vec!["正答".to_string(), "正しい答え".to_string(), "答え".to_string()],
+            },
+            SynonymGroup {
+                nuance: "正しい解釈".to_string(),
+                synonyms: vec!["適切な解釈".to_string(), "正しい理解".to_string()],
+            },
+        ]);
+    }
+}
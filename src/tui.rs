@@ -0,0 +1,434 @@
+use crate::{BACKENDS, Dictionary, DictionaryEntry, DictionaryResult, dictionary_by_name};
+use crate::format::metadata_line;
+use crate::pitch_accent;
+use crate::reference::{Reference, ReferenceKind};
+use crate::sense::Sense;
+use crate::session::Session;
+use crate::theme;
+use crate::wrap;
+use ratatui::DefaultTerminal;
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+
+const HELP: &str = "/ search  ↑↓ select  PgUp/PgDn scroll  1-9 follow reference  b back  Tab backend  q quit";
+
+#[derive(PartialEq, Eq)]
+enum Mode {
+    Browse,
+    Input,
+}
+
+struct App {
+    session: Session,
+    dictionary: Box<dyn Dictionary>,
+    mode: Mode,
+    query: String,
+    input: String,
+    entries: Vec<DictionaryEntry>,
+    list_state: ListState,
+    scroll: u16,
+    /// Referenced sense to mark after following a reference
+    highlight_sense: Option<usize>,
+    status: String,
+    /// Previous queries, for going back after following a synonym
+    back: Vec<String>,
+    quit: bool,
+}
+
+/// Full-screen browser: matched headwords on the left, the selected entry on the right
+pub fn run(session: Session, dictionary: Box<dyn Dictionary>, initial_term: Option<&str>) -> DictionaryResult<()> {
+    let mut app = App {
+        session,
+        dictionary,
+        mode: if initial_term.is_some() { Mode::Browse } else { Mode::Input },
+        query: String::new(),
+        input: String::new(),
+        entries: Vec::new(),
+        list_state: ListState::default(),
+        scroll: 0,
+        highlight_sense: None,
+        status: HELP.to_string(),
+        back: Vec::new(),
+        quit: false,
+    };
+
+    let mut terminal = ratatui::try_init()?;
+    if let Some(term) = initial_term {
+        app.search(&mut terminal, term.to_string());
+    }
+    let result = app.event_loop(&mut terminal);
+    ratatui::try_restore()?;
+    result
+}
+
+impl App {
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> DictionaryResult<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match self.mode {
+                Mode::Input => match key.code {
+                    KeyCode::Enter => {
+                        let term = self.input.trim().to_string();
+                        self.mode = Mode::Browse;
+                        if !term.is_empty() {
+                            if !self.query.is_empty() {
+                                self.back.push(self.query.clone());
+                            }
+                            self.search(terminal, term);
+                        }
+                    }
+                    KeyCode::Esc => self.mode = Mode::Browse,
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(ch) => self.input.push(ch),
+                    _ => {}
+                },
+                Mode::Browse => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+                    KeyCode::Char('/') | KeyCode::Char('i') => {
+                        self.input.clear();
+                        self.mode = Mode::Input;
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => self.select(1),
+                    KeyCode::Up | KeyCode::Char('k') => self.select(-1),
+                    KeyCode::PageDown | KeyCode::Char('J') => self.scroll = self.scroll.saturating_add(5),
+                    KeyCode::PageUp | KeyCode::Char('K') => self.scroll = self.scroll.saturating_sub(5),
+                    KeyCode::Char('b') | KeyCode::Backspace => {
+                        if let Some(term) = self.back.pop() {
+                            self.search(terminal, term);
+                        }
+                    }
+                    KeyCode::Tab => self.next_backend(terminal),
+                    KeyCode::Char(ch @ '1'..='9') => self.follow_reference(terminal, ch as usize - '1' as usize),
+                    _ => {}
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    fn search(&mut self, terminal: &mut DefaultTerminal, term: String) {
+        self.show_lookup(terminal, term, None);
+    }
+
+    /// Look up `term`, or follow `reference` to it, and show the result
+    fn show_lookup(&mut self, terminal: &mut DefaultTerminal, term: String, reference: Option<&Reference>) {
+        self.status = format!("Searching {} for {}…", self.dictionary.name(), term);
+        let _ = terminal.draw(|frame| self.draw(frame));
+
+        let result = match reference {
+            Some(reference) => self.session.follow(self.dictionary.as_ref(), reference),
+            None => self.session.lookup(self.dictionary.as_ref(), &term),
+        };
+        match result {
+            Ok(entries) => {
+                self.status = if entries.is_empty() {
+                    "No definitions found.".to_string()
+                } else {
+                    HELP.to_string()
+                };
+                self.entries = entries;
+            }
+            Err(e) => {
+                self.status = format!("Error: {}", e);
+                self.entries.clear();
+            }
+        }
+
+        self.query = term;
+        self.list_state.select(if self.entries.is_empty() { None } else { Some(0) });
+        self.scroll = 0;
+        self.highlight_sense = None;
+    }
+
+    fn select(&mut self, offset: isize) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let current = self.list_state.selected().unwrap_or(0) as isize;
+        let next = (current + offset).clamp(0, self.entries.len() as isize - 1);
+        self.list_state.select(Some(next as usize));
+        self.scroll = 0;
+    }
+
+    fn selected_entry(&self) -> Option<&DictionaryEntry> {
+        self.list_state.selected().and_then(|i| self.entries.get(i))
+    }
+
+    fn follow_reference(&mut self, terminal: &mut DefaultTerminal, index: usize) {
+        let Some(reference) = self.selected_entry().and_then(|entry| entry.references.get(index)).cloned() else {
+            return;
+        };
+        self.back.push(self.query.clone());
+        // Narrowed down to the referenced word before the result limit applies
+        self.show_lookup(terminal, reference.term.clone(), Some(&reference));
+
+        // Jump to the referenced sense
+        self.highlight_sense = reference.sense;
+        if let (Some(entry), Some(sense)) = (self.selected_entry(), reference.sense) {
+            self.scroll = sense_line(entry, sense) as u16;
+        }
+    }
+
+    fn next_backend(&mut self, terminal: &mut DefaultTerminal) {
+        // Skip backends that can't be used, like an offline dictionary that isn't installed
+        let current = BACKENDS.iter().position(|name| *name == self.dictionary.name()).unwrap_or(0);
+        let next = (1..BACKENDS.len())
+            .map(|offset| BACKENDS[(current + offset) % BACKENDS.len()])
+            .find_map(|name| dictionary_by_name(name).ok());
+        if let Some(dictionary) = next {
+            self.dictionary = dictionary;
+        }
+        if !self.query.is_empty() {
+            self.search(terminal, self.query.clone());
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [search_area, main_area, status_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ]).areas(frame.area());
+        let [list_area, detail_area] = Layout::horizontal([
+            Constraint::Percentage(30),
+            Constraint::Percentage(70),
+        ]).areas(main_area);
+
+        let (search_text, search_style) = if self.mode == Mode::Input {
+            (format!("{}▏", self.input), themed(theme::current().headword))
+        } else {
+            (self.query.clone(), Style::new())
+        };
+        let search = Paragraph::new(search_text)
+            .style(search_style)
+            .block(Block::bordered().title(format!(" Search ({}) ", self.dictionary.name())));
+        frame.render_widget(search, search_area);
+
+        let items: Vec<ListItem> = self.entries.iter()
+            .map(|entry| ListItem::new(entry.word_reading.clone()))
+            .collect();
+        let list = List::new(items)
+            .block(Block::bordered().title(" Entries "))
+            .highlight_style(themed(theme::current().headword).add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, list_area, &mut self.list_state);
+
+        let text = self.selected_entry()
+            .map(|entry| entry_text(entry, self.highlight_sense))
+            .unwrap_or_default();
+        let detail = Paragraph::new(text)
+            .block(Block::bordered())
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0));
+        frame.render_widget(detail, detail_area);
+
+        frame.render_widget(Line::styled(self.status.clone(), themed(theme::current().muted)), status_area);
+    }
+}
+
+/// Lines shown above the definitions in `entry_text`
+fn header_lines(entry: &DictionaryEntry) -> usize {
+    2 + usize::from(!entry.metadata().is_empty())
+        + usize::from(!entry.pitch_accent.is_empty())
+        + usize::from(!entry.part_of_speech.is_empty())
+}
+
+/// Line of the detail pane where a sense starts (ignoring wrapping)
+fn sense_line(entry: &DictionaryEntry, sense: usize) -> usize {
+    if entry.senses.is_empty() {
+        return header_lines(entry) + sense.saturating_sub(1);
+    }
+    let before: usize = sense_lines(entry, None).iter()
+        .take(sense.saturating_sub(1))
+        .map(Vec::len)
+        .sum();
+    header_lines(entry) + before
+}
+
+/// The sense tree as lines for each numbered sense, laid out like `format::format_senses`
+fn sense_lines(entry: &DictionaryEntry, highlight_sense: Option<usize>) -> Vec<Vec<Line<'static>>> {
+    let theme = theme::current();
+    // A single sense isn't numbered
+    let numbered = entry.senses.len() > 1;
+
+    entry.senses.iter().enumerate()
+        .map(|(i, sense)| {
+            let style = if highlight_sense == Some(i + 1) {
+                themed(theme.gloss).add_modifier(Modifier::REVERSED)
+            } else {
+                themed(theme.gloss)
+            };
+            let number = if numbered { format!("({}) ", i + 1) } else { String::new() };
+            let indent = wrap::width(&number);
+            let mut lines = Vec::new();
+            push_sense_lines(&mut lines, sense, vec![Span::styled(number, themed(theme.sense_number))], indent, style);
+            lines
+        })
+        .collect()
+}
+
+/// A sense's text, then its examples and each sub-sense on lines of their own, indented under the text
+fn push_sense_lines(lines: &mut Vec<Line<'static>>, sense: &Sense, prefix: Vec<Span<'static>>, indent: usize, style: Style) {
+    let theme = theme::current();
+    let mut prefix = Some(prefix);
+    let line_prefix = |prefix: &mut Option<Vec<Span<'static>>>| {
+        prefix.take().unwrap_or_else(|| vec![Span::raw(" ".repeat(indent))])
+    };
+
+    if !sense.text.is_empty() {
+        let mut spans = line_prefix(&mut prefix);
+        if !sense.part_of_speech.is_empty() {
+            spans.push(Span::styled(format!("{} ", sense.part_of_speech), themed(theme.pos)));
+        }
+        spans.push(Span::styled(sense.text.clone(), style));
+        lines.push(Line::from(spans));
+    }
+    if !sense.examples.is_empty() {
+        let mut spans = line_prefix(&mut prefix);
+        spans.push(Span::styled(sense.examples.concat(), themed(theme.example)));
+        lines.push(Line::from(spans));
+    }
+
+    for subsense in &sense.subsenses {
+        let label = format!("{} ", subsense.label);
+        let subsense_indent = indent + wrap::width(&label);
+        let mut spans = line_prefix(&mut prefix);
+        spans.push(Span::styled(label, themed(theme.sense_number)));
+        push_sense_lines(lines, subsense, spans, subsense_indent, themed(theme.gloss));
+    }
+}
+
+fn entry_text(entry: &DictionaryEntry, highlight_sense: Option<usize>) -> Text<'static> {
+    let theme = theme::current();
+    let mut lines = vec![Line::styled(entry.word_reading.clone(), themed(theme.headword).add_modifier(Modifier::BOLD))];
+
+    let metadata = entry.metadata();
+    if !metadata.is_empty() {
+        lines.push(Line::styled(metadata_line(&metadata), themed(theme.muted)));
+    }
+
+    if !entry.pitch_accent.is_empty() {
+        let reading = entry.reading();
+        let accents: Vec<String> = entry.pitch_accent.iter()
+            .map(|&pattern| pitch_accent::describe(&reading, pattern))
+            .collect();
+        lines.push(Line::styled(accents.join(" / "), themed(theme.accent)));
+    }
+
+    if !entry.part_of_speech.is_empty() {
+        lines.push(Line::styled(entry.part_of_speech.clone(), themed(theme.pos)));
+    }
+    lines.push(Line::default());
+
+    if !entry.senses.is_empty() {
+        lines.extend(sense_lines(entry, highlight_sense).into_iter().flatten());
+    } else {
+        for (i, def) in entry.definitions.iter().enumerate() {
+            let style = if highlight_sense == Some(i + 1) {
+                themed(theme.gloss).add_modifier(Modifier::REVERSED)
+            } else {
+                themed(theme.gloss)
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("({}) ", i + 1), themed(theme.sense_number)),
+                Span::styled(def.clone(), style),
+            ]));
+        }
+    }
+
+    // Example sentences, each followed by its translation
+    if !entry.example_sentences.is_empty() {
+        lines.push(Line::default());
+        lines.push(Line::styled("例文:", themed(theme.label)));
+        for example in &entry.example_sentences {
+            lines.push(Line::styled(format!("  {}", example.text), themed(theme.gloss)));
+            if !example.translation.is_empty() {
+                lines.push(Line::styled(format!("  {}", example.translation), themed(theme.example)));
+            }
+        }
+    }
+
+    let synonyms: Vec<(usize, &Reference)> = entry.references.iter().enumerate()
+        .filter(|(_, reference)| reference.kind == ReferenceKind::Synonym)
+        .collect();
+    let others: Vec<(usize, &Reference)> = entry.references.iter().enumerate()
+        .filter(|(_, reference)| reference.kind != ReferenceKind::Synonym)
+        .collect();
+
+    for (label, references) in [("類語:", synonyms), ("参照:", others)] {
+        if references.is_empty() {
+            continue;
+        }
+        lines.push(Line::default());
+        lines.push(Line::styled(label, themed(theme.label)));
+        for (i, reference) in references {
+            let number = if i < 9 { format!("[{}] ", i + 1) } else { "    ".to_string() };
+            let mut target = reference.term.clone();
+            if let Some(sense) = reference.sense {
+                target.push_str(&format!(" ({})", sense));
+            }
+            if reference.kind != ReferenceKind::Synonym {
+                target.push_str(&format!(" {}", reference.kind.label()));
+            }
+            lines.push(Line::from(vec![
+                Span::styled(number, themed(theme.reference_number)),
+                Span::styled(target, themed(theme.gloss)),
+            ]));
+        }
+    }
+
+    Text::from(lines)
+}
+
+/// A theme style as a ratatui style, without colors when color output is off
+fn themed(style: theme::Style) -> Style {
+    let mut themed = Style::new();
+    if let Some(color) = style.color.filter(|_| colored::control::SHOULD_COLORIZE.should_colorize()) {
+        themed = themed.fg(match color {
+            colored::Color::Black => Color::Black,
+            colored::Color::Red => Color::Red,
+            colored::Color::Green => Color::Green,
+            colored::Color::Yellow => Color::Yellow,
+            colored::Color::Blue => Color::Blue,
+            colored::Color::Magenta => Color::Magenta,
+            colored::Color::Cyan => Color::Cyan,
+            colored::Color::White => Color::Gray,
+            colored::Color::BrightBlack => Color::DarkGray,
+            colored::Color::BrightRed => Color::LightRed,
+            colored::Color::BrightGreen => Color::LightGreen,
+            colored::Color::BrightYellow => Color::LightYellow,
+            colored::Color::BrightBlue => Color::LightBlue,
+            colored::Color::BrightMagenta => Color::LightMagenta,
+            colored::Color::BrightCyan => Color::LightCyan,
+            colored::Color::BrightWhite => Color::White,
+            colored::Color::TrueColor { r, g, b } => Color::Rgb(r, g, b),
+        });
+    }
+    if style.bold {
+        themed = themed.add_modifier(Modifier::BOLD);
+    }
+    if style.italic {
+        themed = themed.add_modifier(Modifier::ITALIC);
+    }
+    if style.underline {
+        themed = themed.add_modifier(Modifier::UNDERLINED);
+    }
+    if style.dimmed {
+        themed = themed.add_modifier(Modifier::DIM);
+    }
+    themed
+}
@@ -1,27 +1,40 @@
-use crate::{DictionaryEntry, DictionaryResult};
-use reqwest::blocking::get;
-use scraper::{Html, Selector, ElementRef};
-use colored::*;
+use crate::{Dictionary, DictionaryEntry, DictionaryResult};
+use crate::format::{FormatOptions, format_entries};
+use crate::http;
+use crate::reference::{Reference, ReferenceKind};
+use crate::sense::Sense;
+use scraper::{Html, Node, Selector, ElementRef};
+use std::iter::Peekable;
+
+pub const SOURCE: &str = "weblio";
+
+pub struct Weblio;
+
+impl Dictionary for Weblio {
+    fn name(&self) -> &'static str {
+        SOURCE
+    }
+
+    fn lookup(&self, term: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
+        lookup_weblio(term)
+    }
+}
 
 pub fn search_weblio(term: &str) -> DictionaryResult<String> {
+    let entries = lookup_weblio(term)?;
+    Ok(format_entries(&entries, &FormatOptions::default()))
+}
+
+pub fn lookup_weblio(term: &str) -> DictionaryResult<Vec<DictionaryEntry>> {
     let url = format!("https://www.weblio.jp/content/{}", term);
     
-    let response = get(&url)?;
-    let html = response.text()?;
+    let Some(html) = http::get_page(SOURCE, &url)? else {
+        return Ok(Vec::new());
+    };
     
     let document = Html::parse_document(&html);
     
-    
-    let entries = parse_weblio_entries(&document)?;
-    
-    if entries.is_empty() {
-        Ok("No definitions found.".to_string())
-    } else {
-        let formatted_entries: Vec<String> = entries.iter()
-            .map(|entry| format_weblio_entry(entry))
-            .collect();
-        Ok(formatted_entries.join("\n\n"))
-    }
+    parse_weblio_entries(&document)
 }
 
 fn parse_weblio_entries(document: &Html) -> DictionaryResult<Vec<DictionaryEntry>> {
@@ -40,6 +53,8 @@ fn parse_weblio_entries(document: &Html) -> DictionaryResult<Vec<DictionaryEntry
 }
 
 fn parse_single_entry(header: ElementRef, _document: &Html) -> DictionaryResult<Option<DictionaryEntry>> {
+    let dictionary = dictionary_name(header);
+
     // Extract kanji/word from title attribute
     let word = header.value().attr("title").unwrap_or("").to_string();
     
@@ -52,27 +67,26 @@ fn parse_single_entry(header: ElementRef, _document: &Html) -> DictionaryResult<
         return Ok(None);
     }
     
-    // Find the content div that follows this header
-    let mut content_div = None;
-    
-    // Look for the content div by traversing all siblings
-    let mut current_node = header.next_sibling();
-    while let Some(node) = current_node {
-        if let Some(element) = ElementRef::wrap(node) {
-            let class_attr = element.value().attr("class").unwrap_or("");
-            if element.value().name() == "div" && class_attr.contains("Sgkdj") {
-                content_div = Some(element);
-                break;
-            }
-        }
-        current_node = node.next_sibling();
-    }
-    
-    if let Some(content) = content_div {
+    if let Some(content) = content_div(header) {
         let reading_text = extract_reading_from_content(&content);
         let part_of_speech = extract_part_of_speech(&content);
-        let definitions = extract_definitions(&content);
+        let mut senses = extract_senses(&content);
+        let definitions = if senses.is_empty() {
+            let definitions = extract_definitions(&content);
+            // Unnumbered definitions can still end in examples
+            senses = definitions.iter().map(|definition| Sense { text: definition.clone(), ..Sense::default() }).collect();
+            senses.iter_mut().for_each(Sense::finish);
+            if senses.iter().all(|sense| sense.examples.is_empty()) {
+                senses.clear();
+            }
+            definitions
+        } else {
+            senses.iter().map(Sense::plain).collect()
+        };
         let synonyms = extract_synonyms(&content);
+        let notations = extract_notations(&header_text);
+        let historical_kana = extract_historical_kana(&header_text);
+        let etymology = extract_etymology(&content);
         
         
         let word_reading = if !word.is_empty() && !reading_text.is_empty() {
@@ -84,8 +98,18 @@ fn parse_single_entry(header: ElementRef, _document: &Html) -> DictionaryResult<
         };
         
         if !definitions.is_empty() {
+            let references = synonyms.iter()
+                .map(|synonym| Reference::new(ReferenceKind::Synonym, synonym.clone()))
+                .collect();
             let entry = DictionaryEntry::new(word_reading, part_of_speech, definitions)
-                .with_synonyms(synonyms);
+                .with_synonyms(synonyms)
+                .with_references(references)
+                .with_senses(senses)
+                .with_notations(notations)
+                .with_historical_kana(&historical_kana)
+                .with_etymology(&etymology)
+                .with_source(SOURCE)
+                .with_dictionary(&dictionary);
             return Ok(Some(entry));
         }
     }
@@ -93,15 +117,133 @@ fn parse_single_entry(header: ElementRef, _document: &Html) -> DictionaryResult<
     Ok(None)
 }
 
+/// Sub-dictionary an entry belongs to. Each one is introduced by a `div.pbarT` bar naming it,
+/// before the `div.kijiWrp` holding its entries; the content div's class is the fallback.
+fn dictionary_name(header: ElementRef) -> String {
+    let wrapper = header.ancestors()
+        .filter_map(ElementRef::wrap)
+        .find(|element| element.value().attr("class").is_some_and(|class| class.contains("kijiWrp")));
+
+    if let Some(wrapper) = wrapper {
+        let bar_selector = Selector::parse(".pbarTL").unwrap();
+        let bar = wrapper.prev_siblings()
+            .filter_map(ElementRef::wrap)
+            .find(|element| element.value().attr("class").is_some_and(|class| class.contains("pbarT")));
+        if let Some(bar) = bar {
+            let name = bar.select(&bar_selector).next().unwrap_or(bar).text().collect::<String>();
+            if !name.trim().is_empty() {
+                return name.trim().to_string();
+            }
+        }
+    }
+
+    content_div(header)
+        .and_then(|content| content.value().attr("class"))
+        .map(|class| dictionary_for_class(class).to_string())
+        .unwrap_or_default()
+}
+
+/// Content div classes of the sub-dictionaries weblio shows, with their names.
+const DICTIONARIES: &[(&str, &str)] = &[
+    ("Sgkdj", "デジタル大辞泉"),
+    ("Ssdjj", "三省堂 大辞林"),
+    ("Jtnhj", "実用日本語表現辞典"),
+    ("Wkpja", "ウィキペディア"),
+    ("Kkjjt", "漢字辞典"),
+    ("Nhgkt", "日本語表現辞典"),
+];
+
+fn dictionary_for_class(class: &str) -> &'static str {
+    DICTIONARIES.iter()
+        .find(|(prefix, _)| class.contains(prefix))
+        .map(|(_, name)| *name)
+        .unwrap_or("")
+}
+
+/// The content div following a header, before the next header. Only divs with a known
+/// per-dictionary class count, so ads and share buttons in between are skipped.
+fn content_div(header: ElementRef) -> Option<ElementRef> {
+    header.next_siblings()
+        .filter_map(ElementRef::wrap)
+        .take_while(|element| element.value().name() != "h2")
+        .find(|element| {
+            element.value().name() == "div"
+                && element.value().attr("class").is_some_and(|class| !dictionary_for_class(class).is_empty())
+        })
+}
+
 fn extract_reading_from_header(header_text: &str) -> String {
-    // Extract reading from format like "せい‐かい【正解】"
-    if let Some(bracket_pos) = header_text.find('【') {
-        header_text[..bracket_pos].replace('‐', "")
-    } else {
-        header_text.to_string()
+    // Extract reading from format like "せい‐かい【正解】" or "おも・う〔おもふ〕【思う】"
+    let reading = match header_text.find('【') {
+        Some(bracket_pos) => &header_text[..bracket_pos],
+        None => header_text,
+    };
+    let reading = match reading.find('〔') {
+        Some(kana_pos) => &reading[..kana_pos],
+        None => reading,
+    };
+    reading.replace(['‐', '・'], "")
+}
+
+/// Written forms in a header like "おもう【思う／▽想う／×憶う】", when there is more than one.
+/// ▽ and × (readings and kanji outside the jōyō list) are dropped.
+fn extract_notations(header_text: &str) -> Vec<String> {
+    let (Some(open), Some(close)) = (header_text.find('【'), header_text.find('】')) else {
+        return Vec::new();
+    };
+    if open > close {
+        return Vec::new();
+    }
+
+    let notations: Vec<String> = header_text[open + '【'.len_utf8()..close]
+        .split('／')
+        .map(|form| form.replace(['▽', '×', '‐'], "").trim().to_string())
+        .filter(|form| !form.is_empty())
+        .collect();
+    if notations.len() > 1 { notations } else { Vec::new() }
+}
+
+/// Historical kana given in 〔〕 after the reading, e.g. "おもう〔おもふ〕【思う】"
+fn extract_historical_kana(header_text: &str) -> String {
+    let before_word = header_text.split('【').next().unwrap_or("");
+    match (before_word.find('〔'), before_word.find('〕')) {
+        (Some(open), Some(close)) if open < close => {
+            before_word[open + '〔'.len_utf8()..close].replace(['‐', '・'], "").trim().to_string()
+        }
+        _ => String::new(),
     }
 }
 
+/// Word origin, from a ［語源］ note or a leading 《…》 like 《「思ひ」の転》.
+/// 《「いぞん」とも》 gives another reading instead, see `extract_reading_from_content`.
+fn extract_etymology(content: &ElementRef) -> String {
+    let p_selector = Selector::parse("p").unwrap();
+    for p in content.select(&p_selector) {
+        let p_text = p.text().collect::<String>();
+
+        for marker in ["［語源］", "〔語源〕"] {
+            if let Some(start) = p_text.find(marker) {
+                let note = p_text[start + marker.len()..].trim();
+                let end = note.find('。').map(|end| end + '。'.len_utf8()).unwrap_or(note.len());
+                return note[..end].trim().to_string();
+            }
+        }
+
+        let mut rest = p_text.as_str();
+        while let Some(start) = rest.find('《') {
+            let Some(end) = rest[start..].find('》').map(|end| start + end) else {
+                break;
+            };
+            let note = rest[start + '《'.len_utf8()..end].trim();
+            if !note.is_empty() && !note.ends_with("とも") {
+                return note.to_string();
+            }
+            rest = &rest[end + '》'.len_utf8()..];
+        }
+    }
+    String::new()
+}
+
 fn extract_reading_from_content(content: &ElementRef) -> String {
     let mut readings = Vec::new();
     
@@ -109,30 +251,28 @@ fn extract_reading_from_content(content: &ElementRef) -> String {
     let p_selector = Selector::parse("p").unwrap();
     for p in content.select(&p_selector) {
         let p_text = p.text().collect::<String>();
-        if p_text.contains("読み方：") {
-            if let Some(start) = p_text.find("読み方：") {
-                let after_marker = &p_text[start + "読み方：".len()..];
-                readings.push(after_marker.trim().to_string());
-            }
+        if let Some(start) = p_text.find("読み方：") {
+            let after_marker = &p_text[start + "読み方：".len()..];
+            readings.push(after_marker.trim().to_string());
         }
         
         // Look for alternative readings like 《「いぞん」とも》
-        if let Some(start) = p_text.find("《「") {
-            if let Some(end) = p_text.find("」とも》") {
-                let alt_reading = &p_text[start + "《「".len()..end];
-                if !alt_reading.is_empty() {
-                    readings.push(alt_reading.to_string());
-                }
+        if let Some(start) = p_text.find("《「")
+            && let Some(end) = p_text.find("」とも》")
+        {
+            let alt_reading = &p_text[start + "《「".len()..end];
+            if !alt_reading.is_empty() {
+                readings.push(alt_reading.to_string());
             }
         }
         
         // Look for pronunciation notes like 「ふいんき」と発音する
-        if let Some(start) = p_text.find("「") {
-            if let Some(end) = p_text.find("」と発音する") {
-                let alt_reading = &p_text[start + "「".len()..end];
-                if !alt_reading.is_empty() && alt_reading.chars().all(|c| "あいうえおかきくけこさしすせそたちつてとなにぬねのはひふへほまみむめもやゆよらりるれろわをんがぎぐげござじずぜぞだぢづでどばびぶべぼぱぴぷぺぽゃゅょっー".contains(c)) {
-                    readings.push(alt_reading.to_string());
-                }
+        if let Some(start) = p_text.find("「")
+            && let Some(end) = p_text.find("」と発音する")
+        {
+            let alt_reading = &p_text[start + "「".len()..end];
+            if !alt_reading.is_empty() && alt_reading.chars().all(|c| "あいうえおかきくけこさしすせそたちつてとなにぬねのはひふへほまみむめもやゆよらりるれろわをんがぎぐげござじずぜぞだぢづでどばびぶべぼぱぴぷぺぽゃゅょっー".contains(c)) {
+                readings.push(alt_reading.to_string());
             }
         }
     }
@@ -160,82 +300,155 @@ fn extract_part_of_speech(content: &ElementRef) -> String {
     String::new()
 }
 
-fn extract_definitions(content: &ElementRef) -> Vec<String> {
-    let mut definitions = Vec::new();
-    
-    // Look for numbered definitions marked with <b>１</b>, <b>２</b>, etc.
-    let b_selector = Selector::parse("b").unwrap();
-    let mut numbered_defs = Vec::new();
-    
-    for b_tag in content.select(&b_selector) {
-        let b_text = b_tag.text().collect::<String>();
-        if b_text.trim().chars().all(|c| "１２３４５６７８９０".contains(c)) {
-            // This is a numbered definition marker
-            if let Some(parent) = b_tag.parent() {
-                if let Some(p_element) = ElementRef::wrap(parent) {
-                    if p_element.value().name() == "p" {
-                        let p_text = p_element.text().collect::<String>();
-                        // Extract text after the number
-                        if let Some(start) = p_text.find(&b_text) {
-                            let after_number = &p_text[start + b_text.len()..];
-                            let def = after_number.trim().to_string();
-                            if !def.is_empty() {
-                                numbered_defs.push(def);
-                            }
-                        }
-                    }
+/// Pieces of an entry's content in document order: sense markers and the text between them
+enum SenseToken {
+    Marker { level: usize, label: String },
+    Text(String),
+}
+
+/// Numbered senses (`<b>１</b>`) with their sub-senses (①, then ㋐ or ア), examples split out
+fn extract_senses(content: &ElementRef) -> Vec<Sense> {
+    let mut tokens = Vec::new();
+    collect_sense_tokens(*content, &mut tokens);
+
+    let mut markers: Vec<(usize, Sense)> = Vec::new();
+    for token in tokens {
+        match token {
+            SenseToken::Marker { level, label } => markers.push((level, Sense::new(&label))),
+            // Text before the first marker is the reading and part of speech
+            SenseToken::Text(text) => {
+                if let Some((_, sense)) = markers.last_mut() {
+                    sense.text.push_str(&text);
                 }
             }
         }
     }
-    
-    if !numbered_defs.is_empty() {
-        definitions.extend(numbered_defs);
-    } else {
-        // Look for definition paragraphs
-        let p_selector = Selector::parse("p").unwrap();
-        for p in content.select(&p_selector) {
-            let p_text = p.text().collect::<String>();
-            let cleaned = p_text.trim();
-            
-            
-            // Skip reading markers
-            if cleaned.starts_with("読み方：") {
-                continue;
+
+    // Markers without a higher level one above them are the senses themselves
+    let mut senses = nest_senses(&mut markers.into_iter().peekable(), 0);
+    senses.iter_mut().for_each(Sense::finish);
+    senses.retain(|sense| !sense.text.is_empty() || !sense.subsenses.is_empty());
+    senses
+}
+
+/// Senses deeper than `parent`, each taking the deeper markers after it as its sub-senses
+fn nest_senses(markers: &mut Peekable<impl Iterator<Item = (usize, Sense)>>, parent: usize) -> Vec<Sense> {
+    let mut senses = Vec::new();
+    while let Some((level, mut sense)) = markers.next_if(|(level, _)| *level > parent) {
+        sense.subsenses = nest_senses(markers, level);
+        senses.push(sense);
+    }
+    senses
+}
+
+fn collect_sense_tokens(element: ElementRef, tokens: &mut Vec<SenseToken>) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => split_circled_markers(text, tokens),
+            Node::Element(_) => {
+                let Some(child) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                if child.value().attr("class").is_some_and(|class| class.contains("synonymsUnderDict")) {
+                    continue;
+                }
+                if child.value().name() == "b"
+                    && let Some(level) = marker_level(child.text().collect::<String>().trim())
+                {
+                    tokens.push(SenseToken::Marker { level, label: child.text().collect::<String>().trim().to_string() });
+                    continue;
+                }
+                collect_sense_tokens(child, tokens);
             }
-            
-            // For paragraphs that contain part of speech markers, extract the definition part
-            if cleaned.contains("［") && cleaned.contains("］") {
-                // Find the end of the part of speech section - look for either ）or 》
-                if let Some(pos) = cleaned.find("》") {
-                    let chars: Vec<char> = cleaned.chars().collect();
-                    let char_pos = cleaned[..pos].chars().count();
-                    if char_pos + 1 < chars.len() {
-                        let after_pos: String = chars[char_pos + 1..].iter().collect();
-                        let def = after_pos.trim().to_string();
-                        if !def.is_empty() && def.len() > 10 {
-                            definitions.push(def);
-                        }
-                    }
-                } else if let Some(pos) = cleaned.find("）") {
-                    let chars: Vec<char> = cleaned.chars().collect();
-                    let char_pos = cleaned[..pos].chars().count();
-                    if char_pos + 1 < chars.len() {
-                        let after_pos: String = chars[char_pos + 1..].iter().collect();
-                        let def = after_pos.trim().to_string();
-                        if !def.is_empty() && def.len() > 10 {
-                            definitions.push(def);
-                        }
+            _ => {}
+        }
+    }
+}
+
+/// 1 for numbered senses (１, ２, ...), 2 for circled numbers (①, ②, ...) and 3 for
+/// sub-senses (ア, イ, ... or ㋐, ㋑, ...)
+fn marker_level(text: &str) -> Option<usize> {
+    if !text.is_empty() && text.chars().all(|c| "１２３４５６７８９０".contains(c)) {
+        return Some(1);
+    }
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if is_circled_number(c) => Some(2),
+        (Some(c), None) if is_circled_katakana(c) || "アイウエオカキクケコサシスセソ".contains(c) => Some(3),
+        _ => None,
+    }
+}
+
+fn is_circled_number(c: char) -> bool {
+    ('\u{2460}'..='\u{2473}').contains(&c)
+}
+
+fn is_circled_katakana(c: char) -> bool {
+    ('\u{32D0}'..='\u{32FE}').contains(&c)
+}
+
+/// Text nodes can carry ①② and ㋐㋑ markers inline rather than in `<b>` tags
+fn split_circled_markers(text: &str, tokens: &mut Vec<SenseToken>) {
+    let mut rest = text;
+    while let Some((offset, marker)) = rest.char_indices().find(|(_, c)| is_circled_number(*c) || is_circled_katakana(*c)) {
+        if offset > 0 {
+            tokens.push(SenseToken::Text(rest[..offset].to_string()));
+        }
+        let label = marker.to_string();
+        tokens.push(SenseToken::Marker { level: marker_level(&label).unwrap_or(3), label });
+        rest = &rest[offset + marker.len_utf8()..];
+    }
+    if !rest.is_empty() {
+        tokens.push(SenseToken::Text(rest.to_string()));
+    }
+}
+
+/// Definition paragraphs of entries without numbered senses
+fn extract_definitions(content: &ElementRef) -> Vec<String> {
+    let mut definitions = Vec::new();
+
+    let p_selector = Selector::parse("p").unwrap();
+    for p in content.select(&p_selector) {
+        let p_text = p.text().collect::<String>();
+        let cleaned = p_text.trim();
+        
+        
+        // Skip reading markers
+        if cleaned.starts_with("読み方：") {
+            continue;
+        }
+        
+        // For paragraphs that contain part of speech markers, extract the definition part
+        if cleaned.contains("［") && cleaned.contains("］") {
+            // Find the end of the part of speech section - look for either ）or 》
+            if let Some(pos) = cleaned.find("》") {
+                let chars: Vec<char> = cleaned.chars().collect();
+                let char_pos = cleaned[..pos].chars().count();
+                if char_pos + 1 < chars.len() {
+                    let after_pos: String = chars[char_pos + 1..].iter().collect();
+                    let def = after_pos.trim().to_string();
+                    if !def.is_empty() && def.len() > 10 {
+                        definitions.push(def);
                     }
                 }
-            } else if !cleaned.is_empty() && 
-                      cleaned.len() > 10 &&
-                      !cleaned.chars().all(|c| "１２３４５６７８９０".contains(c)) {
-                // Check if this looks like a definition (contains Japanese characters)
-                if cleaned.chars().any(|c| c.is_ascii_punctuation() || "。、".contains(c)) {
-                    definitions.push(cleaned.to_string());
+            } else if let Some(pos) = cleaned.find("）") {
+                let chars: Vec<char> = cleaned.chars().collect();
+                let char_pos = cleaned[..pos].chars().count();
+                if char_pos + 1 < chars.len() {
+                    let after_pos: String = chars[char_pos + 1..].iter().collect();
+                    let def = after_pos.trim().to_string();
+                    if !def.is_empty() && def.len() > 10 {
+                        definitions.push(def);
+                    }
                 }
             }
+        } else if !cleaned.is_empty() && 
+                  cleaned.len() > 10 &&
+                  !cleaned.chars().all(|c| "１２３４５６７８９０".contains(c)) {
+            // Check if this looks like a definition (contains Japanese characters)
+            if cleaned.chars().any(|c| c.is_ascii_punctuation() || "。、".contains(c)) {
+                definitions.push(cleaned.to_string());
+            }
         }
     }
     
@@ -256,40 +469,156 @@ fn extract_synonyms(content: &ElementRef) -> Vec<String> {
     synonyms
 }
 
-fn format_weblio_entry(entry: &DictionaryEntry) -> String {
-    let mut result = String::new();
-    
-    // Word and reading in cyan
-    result.push_str(&entry.word_reading.bright_cyan().to_string());
-    result.push('\n');
-    
-    // Part of speech in yellow (if available)
-    if !entry.part_of_speech.is_empty() {
-        result.push_str(&entry.part_of_speech.bright_yellow().to_string());
-        result.push('\n');
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn senses(html: &str) -> Vec<Sense> {
+        let document = Html::parse_fragment(html);
+        let content = document.select(&Selector::parse("div").unwrap()).next().unwrap();
+        extract_senses(&content)
     }
-    
-    // Format definitions
-    if entry.definitions.len() == 1 {
-        result.push_str(&format!("  {}", entry.definitions[0].bright_white()));
-    } else {
-        for (i, def) in entry.definitions.iter().enumerate() {
-            if i > 0 {
-                result.push('\n');
-            }
-            result.push_str(&format!("  {} {}", 
-                format!("({})", i + 1).bright_magenta(),
-                def.bright_white()));
-        }
+
+    const RESULTS: &str = "<div class=\"pbarT\"><div class=\"pbarTL\">デジタル大辞泉</div></div>\
+        <div class=\"kijiWrp\"><div class=\"kiji\">\
+        <h2 class=\"midashigo\" title=\"思う\">おもう【思う】</h2>\
+        <div class=\"adBox\">広告</div>\
+        <div class=\"Sgkdj\"><p>［動ワ五］</p><p>心に感じる。</p></div>\
+        </div></div>\
+        <div class=\"kiji\">\
+        <h2 class=\"midashigo\" title=\"想う\">おもう【想う】</h2>\
+        <div class=\"shareBtn\">共有</div>\
+        <div class=\"Ssdjj\"><p>心に浮かべる。</p></div>\
+        <h2 class=\"midashigo\" title=\"念う\">おもう【念う】</h2>\
+        <div class=\"adBox\">広告</div>\
+        </div>";
+
+    fn headers(document: &Html) -> Vec<ElementRef<'_>> {
+        document.select(&Selector::parse("h2.midashigo").unwrap()).collect()
     }
-    
-    // Add synonyms if available
-    if !entry.synonyms.is_empty() {
-        result.push('\n');
-        result.push_str(&format!("  {} {}", 
-            "類語:".bright_green(),
-            entry.synonyms.join(", ").bright_white()));
+
+    #[test]
+    fn finds_content_divs_by_dictionary_class() {
+        let document = Html::parse_fragment(RESULTS);
+        let headers = headers(&document);
+        let classes: Vec<Option<&str>> = headers.iter()
+            .map(|header| content_div(*header).and_then(|content| content.value().attr("class")))
+            .collect();
+        // Ads and share buttons are skipped, and content never comes from past the next header
+        assert_eq!(classes, [Some("Sgkdj"), Some("Ssdjj"), None]);
     }
-    
-    result
-}
\ No newline at end of file
+
+    #[test]
+    fn names_dictionaries_from_the_bar_or_the_content_class() {
+        let document = Html::parse_fragment(RESULTS);
+        let names: Vec<String> = headers(&document).into_iter().map(dictionary_name).collect();
+        assert_eq!(names, ["デジタル大辞泉", "三省堂 大辞林", ""]);
+        assert_eq!(dictionary_for_class("kiji Wkpja"), "ウィキペディア");
+        assert_eq!(dictionary_for_class("adBox"), "");
+    }
+
+    #[test]
+    fn skips_headers_without_content() {
+        let document = Html::parse_document(RESULTS);
+        let entries = parse_weblio_entries(&document).unwrap();
+        let dictionaries: Vec<&str> = entries.iter().map(|entry| entry.dictionary.as_str()).collect();
+        assert_eq!(dictionaries, ["デジタル大辞泉", "三省堂 大辞林"]);
+        assert_eq!(entries[0].definitions, ["心に感じる。"]);
+    }
+
+    #[test]
+    fn reads_notations_and_historical_kana_from_headers() {
+        let header = "おも・う〔おもふ〕【思う／▽想う／×憶う】";
+        assert_eq!(extract_reading_from_header(header), "おもう");
+        assert_eq!(extract_notations(header), ["思う", "想う", "憶う"]);
+        assert_eq!(extract_historical_kana(header), "おもふ");
+
+        // A single written form is already the headword
+        assert!(extract_notations("せい‐かい【正解】").is_empty());
+        assert_eq!(extract_historical_kana("せい‐かい【正解】"), "");
+        // 〔〕 after the written form is not historical kana
+        assert_eq!(extract_historical_kana("い‐ぞん【依存】〔名〕"), "");
+    }
+
+    #[test]
+    fn tells_etymology_from_alternative_readings() {
+        let document = Html::parse_document(
+            "<h2 class=\"midashigo\" title=\"思う\">おも・う〔おもふ〕【思う／▽想う】</h2>\
+            <div class=\"Sgkdj\"><p>《「思ひ」の転》［動ワ五(ハ四)］</p><p>心に感じる。</p></div>\
+            <h2 class=\"midashigo\" title=\"依存\">い‐そん【依存】</h2>\
+            <div class=\"Sgkdj\"><p>《「いぞん」とも》［名］(スル)</p><p>他に頼って存在すること。</p></div>\
+            <h2 class=\"midashigo\" title=\"正解\">せい‐かい【正解】</h2>\
+            <div class=\"Ssdjj\"><p>正しい解答。</p><p>［語源］「正しく解く」から。以下略。</p></div>",
+        );
+        let entries = parse_weblio_entries(&document).unwrap();
+        assert_eq!(entries.len(), 3);
+
+        assert_eq!(entries[0].notations, ["思う", "想う"]);
+        assert_eq!(entries[0].historical_kana, "おもふ");
+        assert_eq!(entries[0].etymology, "「思ひ」の転");
+
+        assert_eq!(entries[1].etymology, "");
+        assert_eq!(entries[1].word_reading, "いぞん【依存】");
+        assert!(entries[1].notations.is_empty());
+
+        assert_eq!(entries[2].etymology, "「正しく解く」から。");
+        assert_eq!(entries[2].historical_kana, "");
+    }
+
+    fn labels(senses: &[Sense]) -> Vec<&str> {
+        senses.iter().map(|sense| sense.label.as_str()).collect()
+    }
+
+    #[test]
+    fn nests_katakana_under_numbered_senses() {
+        let senses = senses(
+            "<div class=\"Sgkdj\"><p>［名］(スル)</p>\
+             <p><b>１</b> 正しく解釈すること。「問題を―する」</p>\
+             <p><b>２</b> 答え。<b>ア</b> 正しい答え。<b>イ</b> 望ましい結果。「それが―だ」</p></div>",
+        );
+        assert_eq!(labels(&senses), ["１", "２"]);
+        assert_eq!(senses[0].text, "正しく解釈すること。");
+        assert_eq!(senses[0].examples, ["「問題を―する」"]);
+        assert_eq!(senses[1].text, "答え。");
+        assert_eq!(labels(&senses[1].subsenses), ["ア", "イ"]);
+        assert_eq!(senses[1].subsenses[1].text, "望ましい結果。");
+        assert_eq!(senses[1].subsenses[1].examples, ["「それが―だ」"]);
+    }
+
+    #[test]
+    fn nests_circled_markers_three_levels_deep() {
+        let senses = senses(
+            "<div class=\"Ssdjj\"><p><b>１</b> 心に感じる。①考える。㋐判断する。㋑予想する。②信じる。</p>\
+             <p><b>２</b> 慕う。</p></div>",
+        );
+        assert_eq!(labels(&senses), ["１", "２"]);
+        assert_eq!(labels(&senses[0].subsenses), ["①", "②"]);
+        assert_eq!(labels(&senses[0].subsenses[0].subsenses), ["㋐", "㋑"]);
+        assert_eq!(senses[0].subsenses[0].subsenses[1].text, "予想する。");
+        assert_eq!(senses[0].subsenses[1].text, "信じる。");
+        assert_eq!(senses[1].text, "慕う。");
+        assert_eq!(senses[0].plain(), "心に感じる。 ①考える。 ㋐判断する。 ㋑予想する。 ②信じる。");
+    }
+
+    #[test]
+    fn treats_top_level_sub_markers_as_senses() {
+        let senses = senses("<div class=\"Sgkdj\"><p>［名］ ㋐正しい答え。㋑正しい解釈。</p></div>");
+        assert_eq!(labels(&senses), ["㋐", "㋑"]);
+        assert!(senses.iter().all(|sense| sense.subsenses.is_empty()));
+    }
+
+    #[test]
+    fn finds_no_senses_without_markers() {
+        assert!(senses("<div class=\"Sgkdj\"><p>物事の正しいこと。</p></div>").is_empty());
+    }
+
+    #[test]
+    fn levels_markers() {
+        assert_eq!(marker_level("１２"), Some(1));
+        assert_eq!(marker_level("③"), Some(2));
+        assert_eq!(marker_level("㋒"), Some(3));
+        assert_eq!(marker_level("ウ"), Some(3));
+        assert_eq!(marker_level("ウチ"), None);
+        assert_eq!(marker_level("12"), None);
+    }
+}
@@ -0,0 +1,112 @@
+use ratatui::crossterm::terminal;
+use std::env;
+use std::io::{self, IsTerminal};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Narrowest width worth wrapping to, below this lines are left as they are
+const MIN_WIDTH: usize = 20;
+
+// Characters that shouldn't start or end a line (kinsoku shori)
+const NO_LINE_START: &str = "、。，．・：；？！）」』】〕〉》ー…‥ぁぃぅぇぉっゃゅょゎァィゥェォッャュョヮ々,.:;?!)]}";
+const NO_LINE_END: &str = "（「『【〔〈《([{";
+
+/// Width of the terminal stdout is attached to, None when it isn't a terminal
+pub fn terminal_width() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    if let Some(columns) = env::var("COLUMNS").ok().and_then(|columns| columns.parse().ok()) {
+        return Some(columns);
+    }
+    terminal::size().ok().map(|(columns, _)| columns as usize)
+}
+
+/// Display width, counting full-width characters as two columns
+pub fn width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+/// Wrap `text` to `max_width` columns. Latin text breaks at spaces, Japanese text between
+/// any two characters except before closing and after opening punctuation.
+pub fn wrap(text: &str, max_width: usize) -> Vec<String> {
+    let max_width = max_width.max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for token in tokens(text) {
+        let token_width = width(&token.text);
+        let space = usize::from(token.space_before && !line.is_empty());
+
+        if !line.is_empty() && width(&line) + space + token_width > max_width {
+            lines.push(std::mem::take(&mut line));
+        } else if space == 1 {
+            line.push(' ');
+        }
+
+        if token_width <= max_width {
+            line.push_str(&token.text);
+            continue;
+        }
+
+        // Words longer than a whole line (URLs and the like) are split anywhere
+        for ch in token.text.chars() {
+            if width(&line) + ch.width().unwrap_or(0) > max_width && !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.push(ch);
+        }
+    }
+
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Wrap `text` after `prefix`, indenting continuation lines to line up with the text
+/// after the prefix. Text is left alone when the available width is too narrow.
+pub fn wrap_hanging(prefix: &str, text: &str, max_width: Option<usize>) -> Vec<String> {
+    let indent = width(prefix);
+    let available = match max_width {
+        Some(max_width) if max_width >= indent + MIN_WIDTH => max_width - indent,
+        _ => return vec![text.to_string()],
+    };
+    wrap(text, available)
+}
+
+struct Token {
+    text: String,
+    space_before: bool,
+}
+
+/// Unbreakable pieces of text: Latin words, single Japanese characters, and punctuation
+/// glued to the character it belongs with
+fn tokens(text: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut space_before = false;
+
+    for ch in text.chars() {
+        if ch.is_whitespace() {
+            space_before = true;
+            continue;
+        }
+
+        let wide = ch.width().unwrap_or(0) > 1;
+        let attach = match tokens.last() {
+            None => false,
+            Some(_) if space_before => false,
+            Some(last) => {
+                let last_char = last.text.chars().last().unwrap_or(' ');
+                let last_wide = last_char.width().unwrap_or(0) > 1;
+                NO_LINE_START.contains(ch) || NO_LINE_END.contains(last_char) || (!wide && !last_wide)
+            }
+        };
+
+        match tokens.last_mut() {
+            Some(last) if attach => last.text.push(ch),
+            _ => tokens.push(Token { text: ch.to_string(), space_before }),
+        }
+        space_before = false;
+    }
+    tokens
+}